@@ -0,0 +1,238 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::TError;
+use crate::toolbox::checksum::hex_digest;
+
+pub static MANIFEST_NAME: &str = "bundle.yaml";
+pub static REPOSITORY_NAME: &str = "repository.yaml";
+/// directory in the bundle the version dirs are stored in, laid out like
+/// the exec dir
+static EXEC_DIR_NAME: &str = "exec";
+
+/// lists the tools of a bundle. Bundles are tar archives containing this
+/// manifest, the repository the tools were defined by and their version
+/// dirs.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Manifest {
+    /// platform the tools are built for, e.g. `linux-amd64`
+    platform: String,
+    tools: Vec<BundledTool>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct BundledTool {
+    name: String,
+    version: String,
+    /// package url the tool was installed from
+    url: String,
+    /// sha256 digest of the package, if it was recorded at install
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    /// whether the version was selected with `toolbox use`
+    #[serde(default)]
+    active: bool,
+    /// sha256 digests of the files in the version dir
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn new(platform: String, tools: Vec<BundledTool>) -> Self {
+        Self { platform, tools }
+    }
+    pub fn platform(&self) -> &str {
+        &self.platform
+    }
+    pub fn tools(&self) -> &[BundledTool] {
+        &self.tools
+    }
+}
+
+impl BundledTool {
+    /// describes the version dir `dir`. Hidden files, like the last use
+    /// time and the package record, are left out. This does blocking io.
+    pub fn new(
+        name: String,
+        version: String,
+        url: String,
+        sha256: Option<String>,
+        active: bool,
+        dir: &Path,
+    ) -> Result<Self, TError> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') || !entry.file_type()?.is_file() {
+                continue;
+            }
+            files.insert(file_name, file_digest(&entry.path())?);
+        }
+        Ok(Self {
+            name,
+            version,
+            url,
+            sha256,
+            active,
+            files,
+        })
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// version dir of the tool in a bundle unpacked to `dir`
+    pub fn dir_in(&self, dir: &Path) -> PathBuf {
+        dir.join(EXEC_DIR_NAME).join(&self.name).join(&self.version)
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}/{}", EXEC_DIR_NAME, self.name, self.version)
+    }
+}
+
+/// writes a bundle of the tools in `manifest`, taken from `exec_dir`. This
+/// does blocking io.
+pub fn write(
+    path: &Path,
+    exec_dir: &Path,
+    manifest: &Manifest,
+    repository: &[u8],
+) -> Result<(), TError> {
+    let mut builder = tar::Builder::new(File::create(path)?);
+    builder.follow_symlinks(false);
+    append_file(&mut builder, MANIFEST_NAME, &serde_yaml::to_vec(manifest)?)?;
+    append_file(&mut builder, REPOSITORY_NAME, repository)?;
+    for tool in &manifest.tools {
+        let dir = exec_dir.join(&tool.name).join(&tool.version);
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') {
+                continue;
+            }
+            let name = format!("{}/{}", tool.path(), file_name);
+            builder.append_path_with_name(entry.path(), name)?;
+        }
+    }
+    builder.into_inner()?;
+    Ok(())
+}
+
+fn append_file<W: io::Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), TError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, name, content)?;
+    Ok(())
+}
+
+/// unpacks the bundle at `path` into `dir` and verifies every version dir
+/// against the manifest. This does blocking io.
+pub fn unpack(path: &Path, dir: &Path) -> Result<Manifest, TError> {
+    tar::Archive::new(File::open(path)?).unpack(dir)?;
+    let manifest_path = dir.join(MANIFEST_NAME);
+    if !manifest_path.exists() {
+        return Err(format!(
+            "{}: {} is missing",
+            path.display(),
+            MANIFEST_NAME
+        )
+        .into());
+    }
+    let manifest: Manifest = serde_yaml::from_slice(&fs::read(&manifest_path)?)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    for tool in &manifest.tools {
+        verify(tool, &tool.dir_in(dir))?;
+    }
+    Ok(manifest)
+}
+
+/// checks that `dir` contains exactly the files listed for `tool` with the
+/// listed digests, plus symlinks to them
+fn verify(tool: &BundledTool, dir: &Path) -> Result<(), TError> {
+    let label = format!("{} {}", tool.name, tool.version);
+    let mut names = [&tool.name, &tool.version]
+        .into_iter()
+        .chain(tool.files.keys());
+    if let Some(name) = names.find(|name| !is_plain_name(name)) {
+        return Err(format!("{}: invalid name {:?}", label, name).into());
+    }
+    if !dir.is_dir() {
+        return Err(format!("{}: not found in bundle", label).into());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_symlink() {
+            let target = fs::read_link(entry.path())?;
+            let target = target.to_string_lossy();
+            if !tool.files.contains_key(target.as_ref()) {
+                return Err(format!(
+                    "{}: {} links to {}, which is not in the bundle",
+                    label, file_name, target
+                )
+                .into());
+            }
+            continue;
+        }
+        let expected = tool.files.get(&file_name).ok_or_else(|| {
+            format!("{}: {} is not listed in the manifest", label, file_name)
+        })?;
+        let digest = file_digest(&entry.path())?;
+        if &digest != expected {
+            return Err(format!(
+                "{}: checksum mismatch for {}: expected {}, got {}",
+                label, file_name, expected, digest
+            )
+            .into());
+        }
+    }
+    for file_name in tool.files.keys() {
+        if !dir.join(file_name).is_file() {
+            return Err(format!("{}: {} is missing", label, file_name).into());
+        }
+    }
+    Ok(())
+}
+
+/// whether `name` is a single path component, so joining it to a directory
+/// can't leave that directory
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+}
+
+fn file_digest(path: &Path) -> Result<String, TError> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex_digest(hasher))
+}
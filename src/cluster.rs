@@ -0,0 +1,78 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use kube_client::{config::Kubeconfig, Client};
+use serde::{Deserialize, Serialize};
+
+use crate::{dirs::Dirs, result::Result};
+
+/// how long the server version of a context is cached
+static CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+struct CachedVersion {
+    major: u64,
+    minor: u64,
+    checked_at: u64,
+}
+
+/// kubernetes version of the api server of the current context as
+/// `(major, minor)`. Results are cached per context.
+pub async fn server_version() -> Result<(u64, u64)> {
+    let context = current_context();
+    let cache_path = Dirs::cache_dir()?.join("cluster_versions.yaml");
+    let mut cache: HashMap<String, CachedVersion> = if cache_path.exists() {
+        serde_yaml::from_slice(&std::fs::read(&cache_path)?).unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    if let Some(cached) = cache.get(&context) {
+        if now.saturating_sub(cached.checked_at) < CACHE_TTL.as_secs() {
+            return Ok((cached.major, cached.minor));
+        }
+    }
+
+    let (major, minor) = fetch_server_version(&context).await?;
+    cache.insert(
+        context,
+        CachedVersion {
+            major,
+            minor,
+            checked_at: now,
+        },
+    );
+    std::fs::create_dir_all(Dirs::cache_dir()?)?;
+    std::fs::write(&cache_path, serde_yaml::to_string(&cache)?)?;
+
+    Ok((major, minor))
+}
+
+/// name of the current kube context, empty if there is none
+pub fn current_context() -> String {
+    Kubeconfig::read()
+        .ok()
+        .and_then(|c| c.current_context)
+        .unwrap_or_default()
+}
+
+/// queries the api server of the current context, bypassing the cache
+pub async fn fetch_server_version(context: &str) -> Result<(u64, u64)> {
+    let client = Client::try_default().await?;
+    let info = client.apiserver_version().await.map_err(|e| {
+        format!("Failed to query version of context '{}': {}", context, e)
+    })?;
+    // managed clusters report minors like `24+`
+    let parse = |s: &str| -> Result<u64> {
+        let digits: String =
+            s.chars().take_while(char::is_ascii_digit).collect();
+        digits
+            .parse()
+            .map_err(|_| format!("Malformed server version: {}", s).into())
+    };
+    Ok((parse(&info.major)?, parse(&info.minor)?))
+}
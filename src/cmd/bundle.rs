@@ -0,0 +1,301 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use sha2::{Digest, Sha256};
+use tokio::{fs, task};
+
+use crate::bundle::{self, BundledTool, Manifest, REPOSITORY_NAME};
+use crate::cmd::link::link_missing;
+use crate::cmd::output::{Action, ActionRecord, OutputFormat};
+use crate::cmd::toolbox::print_summary;
+use crate::dirs::Dirs;
+use crate::error::Error;
+use crate::result::Result;
+use crate::toolbox::checksum::hex_digest;
+use crate::toolbox::platform::Platform;
+use crate::toolbox::repository::Repository;
+use crate::toolbox::tool::parse_tool_spec;
+use crate::toolbox::tool::record_package;
+use crate::toolbox::Toolbox;
+
+/// moves installed tools to machines without internet access
+#[derive(Parser, Debug)]
+pub struct Bundle {
+    #[clap(subcommand)]
+    command: BundleCommand,
+}
+
+#[derive(Parser, Debug)]
+enum BundleCommand {
+    Export(Export),
+    Import(Import),
+}
+
+impl Bundle {
+    pub async fn run(
+        &self,
+        toolbox: &Toolbox,
+        output: OutputFormat,
+    ) -> Result<()> {
+        match &self.command {
+            BundleCommand::Export(export) => export.run(toolbox, output).await,
+            BundleCommand::Import(import) => import.run(toolbox, output).await,
+        }
+    }
+}
+
+/// packs installed tools and their dependencies into a tar archive
+#[derive(Parser, Debug)]
+struct Export {
+    /// tools to export as `tool` or `tool@version`. Defaults to the active
+    /// versions of all installed tools.
+    #[clap(long, value_delimiter = ',')]
+    tools: Vec<String>,
+    /// archive to write
+    #[clap(short = 'o', long = "output-file")]
+    file: PathBuf,
+}
+
+impl Export {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let mut selected = vec![];
+        if self.tools.is_empty() {
+            for definition in toolbox.installed_tools().await? {
+                selected.push(toolbox.tool(definition.name())?);
+            }
+        } else {
+            for spec in &self.tools {
+                selected.push(match parse_tool_spec(spec)? {
+                    (name, Some(version_ref)) => {
+                        toolbox.tool_with_version(name, [version_ref])?
+                    }
+                    (name, None) => toolbox.tool(name)?,
+                });
+            }
+        }
+        let mut tools = vec![];
+        for tool in selected {
+            tools.extend(toolbox.dependencies(tool.name())?);
+            tools.push(tool);
+        }
+
+        let platform = toolbox.platform();
+        let mut bundled: Vec<BundledTool> = vec![];
+        for tool in &tools {
+            let version = tool.resolve_version().await?;
+            if bundled
+                .iter()
+                .any(|b| b.name() == tool.name() && b.version() == version)
+            {
+                continue;
+            }
+            let dir = tool.exec_dir_path().await?;
+            if !dir.join(tool.name()).exists() {
+                return Err(format!(
+                    "{} {} is not installed, run `nk toolbox install {}@{}`",
+                    tool.name(),
+                    version,
+                    tool.name(),
+                    version
+                )
+                .into());
+            }
+            let (url, sha256) = match tool.installed_package().await? {
+                Some((url, sha256)) => (url, Some(sha256)),
+                None => {
+                    (tool.definition.package_url(&version, platform)?, None)
+                }
+            };
+            let active =
+                tool.find_active_version().await?.as_ref() == Some(&version);
+            let name = tool.name().to_string();
+            bundled.push(
+                task::spawn_blocking(move || {
+                    BundledTool::new(name, version, url, sha256, active, &dir)
+                })
+                .await?
+                .map_err(|e| e as Error)?,
+            );
+        }
+
+        let records: Vec<_> = bundled
+            .iter()
+            .map(|tool| {
+                let mut record = ActionRecord::new(
+                    tool.name(),
+                    Some(tool.version().to_string()),
+                    Action::Exported,
+                );
+                record.path = Some(self.file.clone());
+                record
+            })
+            .collect();
+        let manifest = Manifest::new(platform.to_string(), bundled);
+        let exec_dir = toolbox.exec_dir_path()?;
+        let file = self.file.clone();
+        let repository = toolbox.repository_source().to_vec();
+        task::spawn_blocking(move || {
+            bundle::write(&file, &exec_dir, &manifest, &repository)
+        })
+        .await?
+        .map_err(|e| e as Error)?;
+        if !output.is_text() {
+            return output.print(&records);
+        }
+        println!(
+            "Exported {} tools to {}",
+            records.len(),
+            self.file.display()
+        );
+        Ok(())
+    }
+}
+
+/// installs the tools of an archive created by `bundle export`
+#[derive(Parser, Debug)]
+struct Import {
+    /// archive to import
+    file: PathBuf,
+    /// replace versions that are already installed
+    #[clap(short, long, action)]
+    force: bool,
+}
+
+impl Import {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let staging = Dirs::data_dir()?.join(".bundle");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).await?;
+        }
+        fs::create_dir_all(&staging).await?;
+        let result = self.import(toolbox, &staging).await;
+        fs::remove_dir_all(&staging).await?;
+        print_summary(&result?, "already installed", output)
+    }
+
+    async fn import(
+        &self,
+        toolbox: &Toolbox,
+        staging: &Path,
+    ) -> Result<Vec<ActionRecord>> {
+        let (file, dir) = (self.file.clone(), staging.to_path_buf());
+        let manifest =
+            task::spawn_blocking(move || bundle::unpack(&file, &dir))
+                .await?
+                .map_err(|e| e as Error)?;
+
+        let platform: Platform = manifest
+            .platform()
+            .parse()
+            .map_err(|e| format!("{}: {}", self.file.display(), e))?;
+        if !platform.is_current() {
+            eprintln!("Importing tools for {}", platform);
+        }
+        let added = self
+            .import_repository(toolbox, &staging.join(REPOSITORY_NAME))
+            .await?;
+        let reloaded;
+        let toolbox = if !added && &platform == toolbox.platform() {
+            toolbox
+        } else {
+            reloaded = toolbox.with_platform(platform.clone()).await?;
+            &reloaded
+        };
+        let exec_dir = toolbox.exec_dir_path()?;
+
+        let mut results = vec![];
+        let mut names = vec![];
+        for tool in manifest.tools() {
+            let definition = toolbox.tool(tool.name());
+            if definition.is_err() {
+                eprintln!(
+                    "Warning: {} is not defined in the repository of this nk",
+                    tool.name()
+                );
+            }
+            let dest = exec_dir.join(tool.name()).join(tool.version());
+            let version = Some(tool.version().to_string());
+            let mut record = if dest.exists() && !self.force {
+                ActionRecord::new(tool.name(), version, Action::Unchanged)
+            } else {
+                if dest.exists() {
+                    fs::remove_dir_all(&dest).await?;
+                }
+                fs::create_dir_all(exec_dir.join(tool.name())).await?;
+                fs::rename(tool.dir_in(staging), &dest).await?;
+                if let Some(sha256) = tool.sha256() {
+                    record_package(&dest, tool.url(), sha256).await?;
+                }
+                ActionRecord::new(tool.name(), version, Action::Installed)
+            };
+            record.path = Some(dest);
+            results.push(record);
+            if let Ok(definition) = definition {
+                if tool.active() {
+                    definition.set_active_version(Some(tool.version())).await?;
+                }
+                names.push(definition.name().to_string());
+                names.extend(
+                    definition
+                        .definition
+                        .binaries()
+                        .iter()
+                        .map(|b| b.name().to_string()),
+                );
+            }
+        }
+
+        // links of other platforms would run the native tools
+        if platform.is_current() {
+            names.sort();
+            names.dedup();
+            let links = link_missing(
+                &toolbox.bin_dir_path()?,
+                &env::current_exe()?,
+                &names,
+            )
+            .await?;
+            for path in links {
+                let name = path.file_name().unwrap().to_string_lossy();
+                let mut record = ActionRecord::new(&name, None, Action::Linked);
+                record.path = Some(path.clone());
+                results.push(record);
+            }
+        }
+        Ok(results)
+    }
+
+    /// keeps the repository of the bundle if it defines tools this nk
+    /// doesn't know. Returns whether it was kept.
+    async fn import_repository(
+        &self,
+        toolbox: &Toolbox,
+        path: &Path,
+    ) -> Result<bool> {
+        if !path.exists() {
+            return Ok(false);
+        }
+        let source = fs::read(path).await?;
+        let repository = Repository::from_slice(&source).map_err(|e| {
+            format!("{}: {}: {}", self.file.display(), REPOSITORY_NAME, e)
+        })?;
+        let added: Vec<_> = repository
+            .tools()
+            .iter()
+            .map(|t| t.name())
+            .filter(|name| toolbox.tool(name).is_err())
+            .collect();
+        if added.is_empty() {
+            return Ok(false);
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(&source);
+        let file_name = format!("{}.yaml", &hex_digest(hasher)[..16]);
+        let dir = Toolbox::repositories_dir_path()?;
+        fs::create_dir_all(&dir).await?;
+        fs::write(dir.join(file_name), &source).await?;
+        eprintln!("Added the definitions of {}", added.join(", "));
+        Ok(true)
+    }
+}
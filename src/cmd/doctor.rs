@@ -0,0 +1,463 @@
+use std::{
+    env, ffi::OsStr, fmt::Display, os::unix::prelude::PermissionsExt,
+    path::Path, process::Stdio, time::Duration,
+};
+
+use clap::Parser;
+use tokio::{fs, io::AsyncReadExt, process::Command};
+
+use crate::cluster;
+use crate::cmd::link::{all_names, link_dirs};
+use crate::cmd::output::{CheckRecord, CheckStatus, OutputFormat};
+use crate::dirs::Dirs;
+use crate::result::Result;
+use crate::toolbox::tool::{Tool, VersionRef};
+use crate::toolbox::Toolbox;
+
+static GITHUB_API_URL: &str = "https://api.github.com/rate_limit";
+static VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// checks the toolbox for broken installations
+#[derive(Parser, Debug)]
+pub struct Doctor {}
+
+/// prints checks as they happen, or collects them for structured output
+struct Report {
+    output: OutputFormat,
+    records: Vec<CheckRecord>,
+    failed: usize,
+}
+
+impl Report {
+    fn new(output: OutputFormat) -> Self {
+        Self {
+            output,
+            records: vec![],
+            failed: 0,
+        }
+    }
+
+    fn record(
+        &mut self,
+        status: CheckStatus,
+        check: impl Display,
+        hint: Option<String>,
+    ) {
+        if !self.output.is_text() {
+            self.records.push(CheckRecord {
+                check: check.to_string(),
+                status,
+                hint,
+            });
+            return;
+        }
+        let label = match status {
+            CheckStatus::Ok => "[ok]  ",
+            CheckStatus::Skipped => "[skip]",
+            CheckStatus::Warning => "[warn]",
+            CheckStatus::Failed => "[fail]",
+        };
+        println!("{} {}", label, check);
+        if let Some(hint) = hint {
+            println!("       hint: {}", hint);
+        }
+    }
+
+    fn pass(&mut self, check: impl Display) {
+        self.record(CheckStatus::Ok, check, None);
+    }
+
+    fn skip(&mut self, check: impl Display) {
+        self.record(CheckStatus::Skipped, check, None);
+    }
+
+    fn warn(&mut self, check: impl Display) {
+        self.record(CheckStatus::Warning, check, None);
+    }
+
+    fn fail(&mut self, check: impl Display, hint: impl Display) {
+        self.failed += 1;
+        self.record(CheckStatus::Failed, check, Some(hint.to_string()));
+    }
+}
+
+impl Doctor {
+    pub async fn run(
+        &self,
+        toolbox: &Toolbox,
+        output: OutputFormat,
+    ) -> Result<()> {
+        let mut report = Report::new(output);
+        check_data_dir(&mut report).await;
+        check_tools(&mut report, toolbox).await?;
+        check_links(&mut report, toolbox).await?;
+        check_link_dirs(&mut report, toolbox).await?;
+        check_network(&mut report, toolbox).await?;
+        check_cluster(&mut report).await;
+
+        output.print(&report.records)?;
+        match report.failed {
+            0 => Ok(()),
+            failed => Err(format!("{} checks failed", failed).into()),
+        }
+    }
+}
+
+async fn check_data_dir(report: &mut Report) {
+    let result = async {
+        let data_dir = Dirs::data_dir()?;
+        fs::create_dir_all(&data_dir).await?;
+        let probe = data_dir.join(".doctor");
+        fs::write(&probe, b"").await?;
+        fs::remove_file(&probe).await?;
+        Ok::<_, crate::error::Error>(data_dir)
+    }
+    .await;
+    match result {
+        Ok(data_dir) => {
+            report.pass(format!("{} is writable", data_dir.display()))
+        }
+        Err(e) => report.fail(
+            format!("data dir is not writable: {}", e),
+            "fix its permissions or point NK_DATA_DIR somewhere else",
+        ),
+    }
+}
+
+async fn check_tools(report: &mut Report, toolbox: &Toolbox) -> Result<()> {
+    let exec_dir_path = toolbox.exec_dir_path()?;
+    if !exec_dir_path.exists() {
+        return Ok(());
+    }
+    let mut tool_dirs = fs::read_dir(&exec_dir_path).await?;
+    while let Some(tool_dir) = tool_dirs.next_entry().await? {
+        if !tool_dir.file_type().await?.is_dir() {
+            continue;
+        }
+        let name = tool_dir.file_name().to_string_lossy().to_string();
+        let tool = match toolbox.tool(&name) {
+            Ok(tool) => tool,
+            Err(_) => {
+                report.fail(
+                    format!("{}: unknown tool", name),
+                    format!("remove {}", tool_dir.path().display()),
+                );
+                continue;
+            }
+        };
+
+        let mut version_dirs = fs::read_dir(tool_dir.path()).await?;
+        while let Some(version_dir) = version_dirs.next_entry().await? {
+            if !version_dir.file_type().await?.is_dir() {
+                continue;
+            }
+            let version = version_dir.file_name().to_string_lossy().to_string();
+            let tool = Tool::new_with_version(
+                tool.definition,
+                toolbox,
+                vec![VersionRef::Specific(version)],
+            );
+            check_version(report, &tool, &version_dir.path()).await?;
+        }
+
+        match toolbox.dependencies(&name) {
+            Ok(deps) => {
+                for dep in deps {
+                    if dep.find_local_versions().await?.is_empty() {
+                        report.fail(
+                            format!(
+                                "{}: dependency {} is missing",
+                                name,
+                                dep.name()
+                            ),
+                            format!("run `nk toolbox install {}`", name),
+                        );
+                    }
+                }
+            }
+            Err(e) => report.fail(
+                format!("{}: {}", name, e),
+                "fix the dependencies in the repository",
+            ),
+        }
+    }
+    Ok(())
+}
+
+async fn check_version(
+    report: &mut Report,
+    tool: &Tool<'_>,
+    version_dir: &Path,
+) -> Result<()> {
+    let version = tool.resolve_version().await?;
+    let label = format!("{} {}", tool.name(), version);
+    let reinstall = format!(
+        "run `nk toolbox install --force {}@{}`",
+        tool.name(),
+        version
+    );
+
+    let mut entries = fs::read_dir(version_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.path().extension() == Some(OsStr::new("part")) {
+            report.fail(
+                format!(
+                    "{}: leftover partial download {}",
+                    label,
+                    entry.path().display()
+                ),
+                format!("remove it or {}", reinstall),
+            );
+        }
+    }
+
+    let platform = tool.platform();
+    let shipped = tool.definition.binaries_for(&platform);
+    let binaries =
+        std::iter::once(tool.name()).chain(shipped.iter().map(|b| b.name()));
+    let mut healthy = true;
+    for binary in binaries {
+        let path = version_dir.join(binary);
+        let metadata = match fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                report.fail(
+                    format!("{}: {} is missing", label, binary),
+                    &reinstall,
+                );
+                healthy = false;
+                continue;
+            }
+        };
+        if metadata.permissions().mode() & 0o111 == 0 {
+            report.fail(
+                format!("{}: {} is not executable", label, binary),
+                &reinstall,
+            );
+            healthy = false;
+            continue;
+        }
+        let mut header = vec![0u8; 64];
+        let n = fs::File::open(&path).await?.read(&mut header).await?;
+        header.truncate(n);
+        match elf_arch(&header) {
+            Some("unknown") => report.warn(format!(
+                "{}: {} is built for an unknown architecture",
+                label, binary
+            )),
+            Some(arch) if arch != platform.arch() => {
+                report.fail(
+                    format!(
+                        "{}: {} is built for {}, not {}",
+                        label,
+                        binary,
+                        arch,
+                        platform.arch()
+                    ),
+                    &reinstall,
+                );
+                healthy = false;
+            }
+            _ => {}
+        }
+    }
+    if !healthy {
+        return Ok(());
+    }
+
+    let version_args = tool.definition.version_args();
+    if !platform.is_current() {
+        report.pass(format!("{} for {}", label, platform));
+        return Ok(());
+    }
+    if version_args.is_empty() {
+        report.pass(&label);
+        return Ok(());
+    }
+    let status = Command::new(version_dir.join(tool.name()))
+        .args(version_args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status();
+    match tokio::time::timeout(VERSION_TIMEOUT, status).await {
+        Ok(Ok(status)) if status.success() => report.pass(&label),
+        Ok(Ok(status)) => report.fail(
+            format!(
+                "{}: `{} {}` failed with {}",
+                label,
+                tool.name(),
+                version_args.join(" "),
+                status
+            ),
+            &reinstall,
+        ),
+        Ok(Err(e)) => {
+            report.fail(format!("{}: failed to run: {}", label, e), &reinstall)
+        }
+        Err(_) => report.fail(
+            format!(
+                "{}: `{} {}` timed out",
+                label,
+                tool.name(),
+                version_args.join(" ")
+            ),
+            &reinstall,
+        ),
+    }
+    Ok(())
+}
+
+/// checks that the symlinks in the bin dir point to this executable
+async fn check_links(report: &mut Report, toolbox: &Toolbox) -> Result<()> {
+    let bin_dir_path = toolbox.bin_dir_path()?;
+    if !bin_dir_path.exists() {
+        return Ok(());
+    }
+    let current_exe = env::current_exe()?.canonicalize()?;
+    let mut entries = fs::read_dir(&bin_dir_path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !fs::symlink_metadata(&path).await?.file_type().is_symlink() {
+            continue;
+        }
+        match path.canonicalize() {
+            Ok(target) if target == current_exe => {}
+            Ok(target) => report.fail(
+                format!("{} points to {}", path.display(), target.display()),
+                "run `nk toolbox link --force`",
+            ),
+            Err(_) => report.fail(
+                format!("{} is a dangling link", path.display()),
+                "remove it and run `nk toolbox link`",
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// checks the links created by `nk toolbox link <dir>`. These dirs are
+/// shared with other programs, so only links named like tools are checked
+/// and only links to another nk count as wrong.
+async fn check_link_dirs(report: &mut Report, toolbox: &Toolbox) -> Result<()> {
+    let current_exe = env::current_exe()?.canonicalize()?;
+    let names = all_names(toolbox);
+    for dir in link_dirs().await? {
+        let hint = format!("run `nk toolbox link --force {}`", dir.display());
+        for name in &names {
+            let path = dir.join(name);
+            match fs::symlink_metadata(&path).await {
+                Ok(metadata) if metadata.file_type().is_symlink() => {}
+                _ => continue,
+            }
+            match path.canonicalize() {
+                Ok(target) if target == current_exe => {}
+                Ok(target) if target.file_name() == current_exe.file_name() => {
+                    report.fail(
+                        format!(
+                            "{} points to {}",
+                            path.display(),
+                            target.display()
+                        ),
+                        &hint,
+                    )
+                }
+                Ok(_) => {}
+                Err(_) => report.fail(
+                    format!("{} is a dangling link", path.display()),
+                    &hint,
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn check_network(report: &mut Report, toolbox: &Toolbox) -> Result<()> {
+    let downloader = toolbox.downloader();
+    if downloader.is_offline() {
+        report.skip("network checks in offline mode");
+        return Ok(());
+    }
+    let hint = "check your network or set a proxy in config.yaml";
+    match downloader.status(GITHUB_API_URL).await {
+        Ok(status) if status.is_success() => {
+            report.pass("GitHub API is reachable")
+        }
+        Ok(status) => report.fail(
+            format!("GitHub API responded with {}", status),
+            "set GITHUB_TOKEN or wait for the rate limit to reset",
+        ),
+        Err(e) => {
+            report.fail(format!("GitHub API is unreachable: {}", e), hint)
+        }
+    }
+
+    for definition in toolbox.installed_tools().await? {
+        let tool = Tool::new_with_version(
+            definition,
+            toolbox,
+            vec![VersionRef::Local],
+        );
+        let version = tool.resolve_version().await?;
+        for url in tool.package_urls(&version)?.iter().skip(1) {
+            match downloader.status(url).await {
+                Ok(status) if status.is_success() => {
+                    report.pass(format!("mirror {} is reachable", url))
+                }
+                Ok(status) => report.fail(
+                    format!("mirror {} responded with {}", url, status),
+                    "fix the mirror in config.yaml",
+                ),
+                Err(e) => report.fail(
+                    format!("mirror {} is unreachable: {}", url, e),
+                    hint,
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn check_cluster(report: &mut Report) {
+    let context = cluster::current_context();
+    if context.is_empty() {
+        report.skip("no current kube context");
+        return;
+    }
+    match cluster::fetch_server_version(&context).await {
+        Ok((major, minor)) => report.pass(format!(
+            "kube context {} is reachable, server version {}.{}",
+            context, major, minor
+        )),
+        Err(e) => report.fail(
+            format!("kube context {} is unreachable: {}", context, e),
+            "check your kubeconfig and vpn",
+        ),
+    }
+}
+
+/// architecture of an ELF executable given its first bytes, named like
+/// `Platform::arch`. None for anything but ELF files, e.g. scripts.
+pub fn elf_arch(header: &[u8]) -> Option<&'static str> {
+    if header.len() < 20 || &header[..4] != b"\x7fELF" {
+        return None;
+    }
+    let big_endian = header[5] == 2;
+    let machine = match big_endian {
+        true => u16::from_be_bytes([header[18], header[19]]),
+        false => u16::from_le_bytes([header[18], header[19]]),
+    };
+    Some(match machine {
+        0x03 => "386",
+        0x28 => "arm",
+        0x3e => "amd64",
+        0xb7 => "arm64",
+        0xf3 => "riscv64",
+        0x15 if big_endian => "ppc64",
+        0x15 => "ppc64le",
+        0x16 => "s390x",
+        _ => "unknown",
+    })
+}
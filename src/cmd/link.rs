@@ -0,0 +1,263 @@
+use std::{
+    collections::BTreeSet,
+    env,
+    io::ErrorKind,
+    os::unix::prelude::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use tokio::fs;
+
+use crate::cmd::output::{Action, ActionRecord, OutputFormat};
+use crate::dirs::Dirs;
+use crate::result::Result;
+use crate::toolbox::Toolbox;
+
+static SHIM_MARKER: &str = "# generated by nk toolbox link";
+/// lists the directories besides the bin dir links were created in
+static LINK_DIRS_FILE_NAME: &str = "link_dirs";
+
+/// links all tools to nk, so they can be called by their names
+#[derive(Parser, Debug)]
+pub struct Link {
+    /// directory to create the links in. Defaults to the bin dir of nk.
+    dir: Option<PathBuf>,
+    /// also link the aliases of tools
+    #[clap(short, long, action)]
+    aliases: bool,
+    /// create shell scripts calling nk instead of symlinks
+    #[clap(long, action)]
+    shim: bool,
+    /// replace existing files that were not created by nk
+    #[clap(short, long, action)]
+    force: bool,
+    /// remove links of tools that no longer exist
+    #[clap(long, action)]
+    prune: bool,
+    /// the nk executable to link to. Defaults to the running one.
+    #[clap(long)]
+    target: Option<PathBuf>,
+}
+
+impl Link {
+    pub async fn run(
+        &self,
+        toolbox: &Toolbox,
+        output: OutputFormat,
+    ) -> Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => toolbox.bin_dir_path()?,
+        };
+        let target = match &self.target {
+            Some(target) => target.clone(),
+            None => env::current_exe()?,
+        };
+        fs::create_dir_all(&dir).await?;
+        if dir != toolbox.bin_dir_path()? {
+            record_link_dir(&dir).await?;
+        }
+
+        let names = names(toolbox, self.aliases);
+        let mut records = vec![];
+        let mut conflicts = 0;
+        for name in &names {
+            let path = dir.join(name);
+            let exists = fs::symlink_metadata(&path).await.is_ok();
+            if exists && !is_managed(&path, &target).await {
+                if !self.force {
+                    let error = format!(
+                        "{} already exists, use --force to replace it",
+                        path.display()
+                    );
+                    match output.is_text() {
+                        true => eprintln!("Conflict: {}", error),
+                        false => {
+                            let mut record =
+                                ActionRecord::new(name, None, Action::Failed);
+                            record.path = Some(path);
+                            record.error = Some(error);
+                            records.push(record);
+                        }
+                    }
+                    conflicts += 1;
+                    continue;
+                }
+            } else if exists && self.is_current(&path, &target).await {
+                let mut record =
+                    ActionRecord::new(name, None, Action::Unchanged);
+                record.path = Some(path);
+                records.push(record);
+                continue;
+            }
+
+            if exists {
+                fs::remove_file(&path).await?;
+            }
+            if self.shim {
+                write_shim(&path, &target, name).await?;
+            } else {
+                fs::symlink(&target, &path).await?;
+            }
+            if output.is_text() {
+                println!("Linked: {}", path.display());
+            }
+            let mut record = ActionRecord::new(name, None, Action::Linked);
+            record.path = Some(path);
+            records.push(record);
+        }
+
+        if self.prune {
+            // aliases stay linked when pruning without --aliases and links
+            // to nk itself, like `~/.local/bin/nk`, are never pruned
+            let mut keep = all_names(toolbox);
+            keep.insert("nk".to_string());
+            keep.extend(
+                target
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string()),
+            );
+            let mut entries = fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let path = entry.path();
+                if keep.contains(&name) || !is_managed(&path, &target).await {
+                    continue;
+                }
+                fs::remove_file(&path).await?;
+                if output.is_text() {
+                    println!("Removed: {}", path.display());
+                }
+                let mut record =
+                    ActionRecord::new(&name, None, Action::Removed);
+                record.path = Some(path);
+                records.push(record);
+            }
+        }
+
+        if !output.is_text() {
+            output.print(&records)?;
+        }
+
+        match conflicts {
+            0 => Ok(()),
+            conflicts => {
+                Err(format!("{} links could not be created", conflicts).into())
+            }
+        }
+    }
+
+    /// whether `path` is already the kind of link this command would create
+    async fn is_current(&self, path: &Path, target: &Path) -> bool {
+        match self.shim {
+            true => is_shim(path).await,
+            false => fs::read_link(path).await.ok().as_deref() == Some(target),
+        }
+    }
+}
+
+/// links each of `names` in `dir` to `target`, skipping names that already
+/// exist. Returns the links created.
+pub async fn link_missing(
+    dir: &Path,
+    target: &Path,
+    names: &[String],
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).await?;
+    let mut created = vec![];
+    for name in names {
+        let path = dir.join(name);
+        if fs::symlink_metadata(&path).await.is_ok() {
+            continue;
+        }
+        fs::symlink(target, &path).await?;
+        created.push(path);
+    }
+    Ok(created)
+}
+
+/// directories besides the bin dir `nk toolbox link` created links in
+pub async fn link_dirs() -> Result<Vec<PathBuf>> {
+    let path = Dirs::data_dir()?.join(LINK_DIRS_FILE_NAME);
+    match fs::read_to_string(&path).await {
+        Ok(content) => Ok(content.lines().map(PathBuf::from).collect()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn record_link_dir(dir: &Path) -> Result<()> {
+    let dir = fs::canonicalize(dir).await?;
+    let mut dirs = link_dirs().await?;
+    if dirs.contains(&dir) {
+        return Ok(());
+    }
+    dirs.push(dir);
+    let content: String = dirs
+        .iter()
+        .map(|dir| format!("{}\n", dir.display()))
+        .collect();
+    let data_dir = Dirs::data_dir()?;
+    fs::create_dir_all(&data_dir).await?;
+    fs::write(data_dir.join(LINK_DIRS_FILE_NAME), content).await?;
+    Ok(())
+}
+
+/// names of all tools and their binaries, including aliases if asked to
+fn names(toolbox: &Toolbox, aliases: bool) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for tool in toolbox.repository().tools() {
+        names.insert(tool.name().to_string());
+        for binary in tool.binaries() {
+            names.insert(binary.name().to_string());
+            if let (true, Some(alias)) = (aliases, binary.alias()) {
+                names.insert(alias.to_string());
+            }
+        }
+        if aliases {
+            names.extend(tool.aliases().iter().cloned());
+        }
+    }
+    names
+}
+
+/// names of all tools, their binaries and all aliases
+pub fn all_names(toolbox: &Toolbox) -> BTreeSet<String> {
+    names(toolbox, true)
+}
+
+/// whether `path` was created by this command, either as a symlink to
+/// `target` or as a shim
+async fn is_managed(path: &Path, target: &Path) -> bool {
+    if fs::read_link(path).await.is_ok() {
+        let resolved = fs::canonicalize(path).await.ok();
+        return resolved.is_some()
+            && resolved == fs::canonicalize(target).await.ok();
+    }
+    is_shim(path).await
+}
+
+async fn is_shim(path: &Path) -> bool {
+    match fs::read_to_string(path).await {
+        Ok(content) => content.lines().nth(1) == Some(SHIM_MARKER),
+        Err(_) => false,
+    }
+}
+
+async fn write_shim(path: &Path, target: &Path, name: &str) -> Result<()> {
+    let content = format!(
+        "#!/bin/sh\n{}\nexec {} {} \"$@\"\n",
+        SHIM_MARKER,
+        quote(&target.to_string_lossy()),
+        quote(name)
+    );
+    fs::write(path, content).await?;
+    fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+    Ok(())
+}
+
+/// quotes `s` for the shell
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use futures::{stream, StreamExt};
+
+use crate::cmd::output::{CheckRecord, CheckStatus, OutputFormat};
+use crate::result::Result;
+use crate::toolbox::lint;
+use crate::toolbox::repository::Repository;
+use crate::toolbox::tool::{Tool, VersionRef};
+use crate::toolbox::{Toolbox, REPOSITORY};
+
+static CONCURRENT_PROBES: usize = 8;
+
+/// checks a repository file for mistakes, e.g. templates that fail on
+/// another platform
+#[derive(Parser, Debug)]
+pub struct Lint {
+    /// repository file to check. Defaults to the built-in one.
+    file: Option<PathBuf>,
+    /// request every package and checksum url of the latest versions
+    #[clap(long, action)]
+    probe: bool,
+}
+
+impl Lint {
+    pub async fn run(
+        &self,
+        toolbox: &Toolbox,
+        output: OutputFormat,
+    ) -> Result<()> {
+        let (name, content) = match &self.file {
+            Some(file) => (file.display().to_string(), std::fs::read(file)?),
+            None => ("built-in repository".to_string(), REPOSITORY.to_vec()),
+        };
+        let repository = Repository::from_slice(&content)
+            .map_err(|e| format!("{}: {}", name, e))?;
+
+        let mut problems = lint::lint(&repository);
+        if self.probe {
+            problems.extend(probe(toolbox, &repository).await);
+        }
+
+        if output.is_text() {
+            for problem in &problems {
+                println!("{}", problem);
+            }
+        } else {
+            let records: Vec<_> = problems
+                .iter()
+                .map(|problem| CheckRecord {
+                    check: problem.clone(),
+                    status: CheckStatus::Failed,
+                    hint: None,
+                })
+                .collect();
+            output.print(&records)?;
+        }
+        match problems.len() {
+            0 => {
+                if output.is_text() {
+                    println!("{}: no problems found", name);
+                }
+                Ok(())
+            }
+            n => Err(format!("{}: {} problems found", name, n).into()),
+        }
+    }
+}
+
+/// requests the urls of the latest version of every tool on every platform
+async fn probe(toolbox: &Toolbox, repository: &Repository) -> Vec<String> {
+    let mut urls = vec![];
+    let mut problems = vec![];
+    for definition in repository.tools() {
+        let tool =
+            Tool::new_with_version(definition, toolbox, [VersionRef::Latest]);
+        match tool.resolve_version().await {
+            Ok(version) => urls.extend(
+                lint::urls(definition, &version)
+                    .into_iter()
+                    .map(|url| (definition.name(), url)),
+            ),
+            Err(e) => problems.push(format!(
+                "{}: latest version not found: {}",
+                definition.name(),
+                e
+            )),
+        }
+    }
+
+    let downloader = toolbox.downloader();
+    let results: Vec<_> = stream::iter(urls)
+        .map(|(name, url)| async move {
+            let status = downloader.status(&url).await;
+            (name, url, status)
+        })
+        .buffered(CONCURRENT_PROBES)
+        .collect()
+        .await;
+    for (name, url, status) in results {
+        match status {
+            Ok(status) if status.is_success() => {}
+            Ok(status) => problems
+                .push(format!("{}: {} responded with {}", name, url, status)),
+            Err(e) => problems.push(format!("{}: {}: {}", name, url, e)),
+        }
+    }
+    problems
+}
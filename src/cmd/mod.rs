@@ -1,6 +1,13 @@
+pub mod bundle;
 pub mod cfg_pack;
 pub mod completion;
 pub mod copy;
+pub mod doctor;
+pub mod link;
+pub mod lint;
+pub mod outdated;
+pub mod output;
+pub mod self_update;
 pub mod shell;
 pub mod tool;
 pub mod toolbox;
@@ -0,0 +1,362 @@
+use std::{
+    env,
+    ops::Range,
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Parser;
+use futures::{stream, StreamExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd::output::{OutdatedRecord, OutputFormat, ReleaseRecord};
+use crate::dirs::Dirs;
+use crate::result::Result;
+use crate::toolbox::tool::{parse_semver, Tool, ToolDefinition, VersionRef};
+use crate::toolbox::Toolbox;
+
+static NOTICE_FILE_NAME: &str = "update_notice.yaml";
+static CONCURRENT_LOOKUPS: usize = 8;
+
+/// compares the installed tools with their latest upstream versions
+#[derive(Parser, Debug)]
+pub struct Outdated {
+    /// print the release notes of every version between the current and
+    /// the latest one
+    #[clap(short, long, action)]
+    notes: bool,
+    /// refreshes the state of the update notice. Started in the background
+    /// by nk itself.
+    #[clap(long, action, hide = true)]
+    refresh_notice: bool,
+}
+
+impl Outdated {
+    pub async fn run(
+        &self,
+        toolbox: &Toolbox,
+        output: OutputFormat,
+    ) -> Result<()> {
+        if self.refresh_notice {
+            return refresh_notice(toolbox).await;
+        }
+
+        let tools = toolbox.installed_tools().await?;
+        let results: Vec<_> = stream::iter(&tools)
+            .map(|definition| self.check(toolbox, definition))
+            .buffered(CONCURRENT_LOOKUPS)
+            .collect()
+            .await;
+        let mut records = vec![];
+        for (definition, result) in tools.iter().zip(results) {
+            match result {
+                Ok(Some(record)) => records.push(record),
+                Ok(None) => {}
+                Err(e) => eprintln!("{}: {}", definition.name(), e),
+            }
+        }
+
+        if !output.is_text() {
+            return output.print(&records);
+        }
+        if records.is_empty() {
+            println!("All tools are up to date");
+            return Ok(());
+        }
+        print_table(&records);
+        for record in &records {
+            print_notes(record);
+        }
+        Ok(())
+    }
+
+    /// compares `definition` with upstream. None if it is up to date or
+    /// no version of it is in use.
+    async fn check(
+        &self,
+        toolbox: &Toolbox,
+        definition: &ToolDefinition,
+    ) -> Result<Option<OutdatedRecord>> {
+        let current = match current_version(toolbox, definition).await {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+        let tool = Tool::new(definition, toolbox);
+        let latest = tool.find_latest_version().await?;
+        if !is_newer(&latest, &current) {
+            return Ok(None);
+        }
+
+        let releases = tool
+            .find_remote_releases(true, |releases| {
+                releases.iter().any(|r| is_same(&r.version, &current))
+            })
+            .await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let age_days = releases
+            .iter()
+            .find(|r| is_same(&r.version, &current))
+            .and_then(|r| r.published_at.as_deref())
+            .and_then(parse_timestamp)
+            .map(|published_at| now.saturating_sub(published_at) / 86400);
+        let releases = self.notes.then(|| {
+            let mut releases: Vec<_> = releases
+                .into_iter()
+                .filter(|r| !r.prerelease)
+                .filter(|r| is_newer(&r.version, &current))
+                .filter(|r| !is_newer(&r.version, &latest))
+                .map(|r| ReleaseRecord {
+                    version: r.version,
+                    published_at: r.published_at,
+                    notes: r.notes,
+                })
+                .collect();
+            releases.sort_by(|a, b| {
+                parse_semver(&b.version).cmp(&parse_semver(&a.version))
+            });
+            releases
+        });
+
+        Ok(Some(OutdatedRecord {
+            name: definition.name().to_string(),
+            current_version: current,
+            latest_version: latest,
+            age_days,
+            releases,
+        }))
+    }
+}
+
+fn print_table(records: &[OutdatedRecord]) {
+    let rows: Vec<_> = records
+        .iter()
+        .map(|r| {
+            let age = match r.age_days {
+                Some(days) => format!("{} days", days),
+                None => "-".to_string(),
+            };
+            [r.name.as_str(), &r.current_version, &r.latest_version, &age]
+                .map(String::from)
+        })
+        .collect();
+    let header = ["TOOL", "CURRENT", "LATEST", "AGE"].map(String::from);
+    let width = |i: usize| {
+        std::iter::once(&header)
+            .chain(&rows)
+            .map(|row| row[i].len())
+            .max()
+            .unwrap_or(0)
+    };
+    let (w0, w1, w2) = (width(0), width(1), width(2));
+    for row in std::iter::once(&header).chain(&rows) {
+        println!("{:w0$} {:w1$} {:w2$} {}", row[0], row[1], row[2], row[3]);
+    }
+}
+
+fn print_notes(record: &OutdatedRecord) {
+    for release in record.releases.iter().flatten() {
+        let date = release
+            .published_at
+            .as_deref()
+            .and_then(|p| p.get(..10))
+            .unwrap_or("unknown date");
+        println!();
+        println!("# {} {} ({})", record.name, release.version, date);
+        match release.notes.as_deref().map(str::trim) {
+            Some(notes) if !notes.is_empty() => println!("{}", notes),
+            _ => println!("No release notes"),
+        }
+    }
+}
+
+/// version of `definition` in use: the one locked or pinned by the
+/// project, otherwise the active one. Never looks up the latest version.
+async fn current_version(
+    toolbox: &Toolbox,
+    definition: &ToolDefinition,
+) -> Option<String> {
+    let version_refs: Vec<_> = toolbox
+        .default_version_refs(definition.name())
+        .into_iter()
+        .filter(|r| !matches!(r, VersionRef::Latest))
+        .collect();
+    if version_refs.is_empty() {
+        return None;
+    }
+    Tool::new_with_version(definition, toolbox, version_refs)
+        .resolve_version()
+        .await
+        .ok()
+}
+
+fn is_same(a: &str, b: &str) -> bool {
+    match (parse_semver(a), parse_semver(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// whether `a` is newer than `b`. Versions that aren't semantic versions
+/// are newer whenever they differ.
+fn is_newer(a: &str, b: &str) -> bool {
+    match (parse_semver(a), parse_semver(b)) {
+        (Some(a), Some(b)) => a > b,
+        _ => a != b,
+    }
+}
+
+/// whether `current` is more than `minor_versions` minor versions behind
+/// `latest`. A newer major version is always behind.
+pub fn is_behind(current: &str, latest: &str, minor_versions: u64) -> bool {
+    match (parse_semver(current), parse_semver(latest)) {
+        (Some(current), Some(latest)) => {
+            latest.major > current.major
+                || (latest.major == current.major
+                    && latest.minor > current.minor + minor_versions)
+        }
+        _ => false,
+    }
+}
+
+/// seconds since the unix epoch of an RFC 3339 timestamp in UTC, as
+/// reported by GitHub, e.g. `2023-03-08T19:56:16Z`
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let field = |range: Range<usize>| -> Option<i64> {
+        timestamp.get(range)?.parse().ok()
+    };
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) =
+        (field(11..13)?, field(14..16)?, field(17..19)?);
+
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    u64::try_from(days * 86400 + hour * 3600 + minute * 60 + second).ok()
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+struct NoticeState {
+    #[serde(default)]
+    checked_at: u64,
+    #[serde(default)]
+    notified_at: u64,
+    /// tools found behind by the last check
+    #[serde(default)]
+    behind: Vec<BehindTool>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+struct BehindTool {
+    name: String,
+    current_version: String,
+    latest_version: String,
+}
+
+impl NoticeState {
+    fn load() -> Result<Self> {
+        let path = Dirs::cache_dir()?.join(NOTICE_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yaml::from_slice(&std::fs::read(&path)?).unwrap_or_default())
+    }
+
+    fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(Dirs::cache_dir()?)?;
+        let path = Dirs::cache_dir()?.join(NOTICE_FILE_NAME);
+        std::fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// prints a notice about tools that fell behind upstream if `update_notice`
+/// is set in config.yaml. Only reports what the previous check found and
+/// starts the next check in the background, so it never delays a command.
+pub fn update_notice(toolbox: &Toolbox) {
+    if let Err(e) = try_update_notice(toolbox) {
+        debug!("update_notice: {}", e);
+    }
+}
+
+fn try_update_notice(toolbox: &Toolbox) -> Result<()> {
+    let notice = match toolbox.config().update_notice() {
+        Some(notice) => notice,
+        None => return Ok(()),
+    };
+    if toolbox.downloader().is_offline() {
+        return Ok(());
+    }
+    let interval = notice.interval_hours() * 60 * 60;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut state = NoticeState::load()?;
+
+    let notify = !state.behind.is_empty()
+        && now.saturating_sub(state.notified_at) >= interval
+        && nix::unistd::isatty(2).unwrap_or(false);
+    if notify {
+        for tool in &state.behind {
+            eprintln!(
+                "Notice: {} {} is behind the latest version {}",
+                tool.name, tool.current_version, tool.latest_version
+            );
+        }
+        eprintln!("Notice: run `nk toolbox outdated --notes` for details");
+        state.notified_at = now;
+    }
+
+    let check = now.saturating_sub(state.checked_at) >= interval;
+    if check {
+        state.checked_at = now;
+    }
+    if notify || check {
+        state.save()?;
+    }
+    if check {
+        Command::new(env::current_exe()?)
+            .args(["toolbox", "outdated", "--refresh-notice"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+    }
+    Ok(())
+}
+
+async fn refresh_notice(toolbox: &Toolbox) -> Result<()> {
+    let notice = match toolbox.config().update_notice() {
+        Some(notice) => notice,
+        None => return Ok(()),
+    };
+    let mut behind = vec![];
+    for definition in toolbox.installed_tools().await? {
+        let current = match current_version(toolbox, definition).await {
+            Some(current) => current,
+            None => continue,
+        };
+        let tool = Tool::new(definition, toolbox);
+        let latest = match tool.find_latest_version().await {
+            Ok(latest) => latest,
+            Err(_) => continue,
+        };
+        if is_behind(&current, &latest, notice.minor_versions()) {
+            behind.push(BehindTool {
+                name: definition.name().to_string(),
+                current_version: current,
+                latest_version: latest,
+            });
+        }
+    }
+
+    let mut state = NoticeState::load()?;
+    state.checked_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    state.behind = behind;
+    state.save()
+}
@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::result::Result;
+
+/// output format of the toolbox subcommands. The records printed as json or
+/// yaml are defined below. Fields are only ever added to them, so scripts
+/// can rely on their schema.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub fn is_text(&self) -> bool {
+        *self == OutputFormat::Text
+    }
+
+    /// prints `records` in a structured format. Does nothing for `Text`,
+    /// which every command prints on its own.
+    pub fn print<T: Serialize + ?Sized>(&self, records: &T) -> Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(records)?)
+            }
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(records)?),
+        }
+        Ok(())
+    }
+}
+
+/// a tool of the repository
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ToolRecord {
+    pub name: String,
+    pub description: String,
+    pub aliases: Vec<String>,
+    /// installed versions, lowest first
+    pub installed_versions: Vec<String>,
+    /// version used when none is pinned
+    pub active_version: Option<String>,
+    /// latest upstream version. Only looked up by `toolbox remote`.
+    pub latest_version: Option<String>,
+    /// upstream versions, newest release first. Only looked up by
+    /// `toolbox remote --all`.
+    pub available_versions: Option<Vec<String>>,
+    /// directory of the active version
+    pub path: Option<PathBuf>,
+}
+
+/// what a command did to a single version of a tool
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ActionRecord {
+    pub name: String,
+    pub version: Option<String>,
+    pub action: Action,
+    /// directory of the version
+    pub path: Option<PathBuf>,
+    /// bytes removed from disk
+    pub size: Option<u64>,
+    /// why the action failed
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Installed,
+    Updated,
+    Unchanged,
+    Removed,
+    WouldRemove,
+    Failed,
+    NotInstalled,
+    Linked,
+    Activated,
+    Locked,
+    Exported,
+}
+
+impl ActionRecord {
+    pub fn new(name: &str, version: Option<String>, action: Action) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+            action,
+            path: None,
+            size: None,
+            error: None,
+        }
+    }
+}
+
+/// the outcome of a check of `toolbox doctor` or a problem found by
+/// `toolbox lint`
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CheckRecord {
+    pub check: String,
+    pub status: CheckStatus,
+    /// how to fix a failed check
+    pub hint: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Skipped,
+    /// doesn't fail `toolbox doctor`
+    Warning,
+    Failed,
+}
+
+/// an installed tool that is behind its latest upstream version
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct OutdatedRecord {
+    pub name: String,
+    /// pinned or active version
+    pub current_version: String,
+    pub latest_version: String,
+    /// days since the current version was released, if known
+    pub age_days: Option<u64>,
+    /// releases after the current version, newest first. Only looked up by
+    /// `toolbox outdated --notes`.
+    pub releases: Option<Vec<ReleaseRecord>>,
+}
+
+/// an upstream release and its notes
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ReleaseRecord {
+    pub version: String,
+    pub published_at: Option<String>,
+    pub notes: Option<String>,
+}
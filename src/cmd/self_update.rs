@@ -0,0 +1,174 @@
+use std::{env, process::Stdio, time::Duration};
+
+use clap::Parser;
+use tokio::{fs, process::Command};
+
+use crate::cmd::output::{Action, ActionRecord, OutdatedRecord, OutputFormat};
+use crate::result::Result;
+use crate::toolbox::platform::Platform;
+use crate::toolbox::tool::{parse_semver, Tool, ToolDefinition, VersionRef};
+use crate::toolbox::Toolbox;
+
+static VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// nk itself, described like any other tool. Releases are built for the
+/// targets in `.github/workflows/release.yaml` and archived with the
+/// `archive-prefix` of `.github/actions/rust-build`. The releases carry no
+/// checksums, so the new executable is only verified by running it.
+static DEFINITION: &str = r#"
+name: nk
+description: Neatkube
+upstream:
+  github_release:
+    repo: withlazers/neatkube
+    file: neatkube-{{version}}-{{os_arch}}.tar.gz
+os_arch_map:
+  linux-amd64: x86_64-unknown-linux-musl
+  linux-arm64: aarch64-unknown-linux-musl
+  linux-386: i686-unknown-linux-musl
+  linux-arm: armv7-unknown-linux-musleabihf
+  darwin-amd64: x86_64-apple-darwin
+  darwin-arm64: aarch64-apple-darwin
+extract:
+  format: tar.gz
+  path: neatkube-{{version}}-{{os_arch}}/bin/nk
+"#;
+
+#[derive(Parser, Debug)]
+#[clap(
+    name = "self-update",
+    about = "updates nk and its tool definitions to a new release"
+)]
+pub struct SelfUpdateCommand {
+    /// version to install. Defaults to the latest release.
+    version: Option<String>,
+    /// only check whether a newer release is available
+    #[clap(long, action, conflicts_with = "version")]
+    check: bool,
+    /// reinstall the running version
+    #[clap(short, long, action)]
+    force: bool,
+    /// print json or yaml records instead of text
+    #[clap(long, value_enum, env = "NK_OUTPUT", default_value = "text")]
+    output: OutputFormat,
+}
+
+impl SelfUpdateCommand {
+    pub async fn run(&self, toolbox: &Toolbox) -> Result<()> {
+        let definition = definition()?;
+        let platform = Platform::current();
+        if definition.os_arch(&platform) == platform.to_string() {
+            return Err(format!("No nk releases for {}", platform).into());
+        }
+        let version_ref = match &self.version {
+            Some(version) if version.starts_with('v') => {
+                VersionRef::Specific(version.clone())
+            }
+            Some(version) => VersionRef::Specific(format!("v{}", version)),
+            None => VersionRef::Latest,
+        };
+        let tool = Tool::new_with_version(&definition, toolbox, [version_ref]);
+        let version = tool.resolve_version().await?;
+        let current = format!("v{}", env!("CARGO_PKG_VERSION"));
+
+        if self.check {
+            let newer = matches!(
+                (parse_semver(&version), parse_semver(&current)),
+                (Some(version), Some(current)) if version > current
+            );
+            if !self.output.is_text() {
+                let records: Vec<_> = newer
+                    .then(|| OutdatedRecord {
+                        name: "nk".to_string(),
+                        current_version: current,
+                        latest_version: version,
+                        age_days: None,
+                        releases: None,
+                    })
+                    .into_iter()
+                    .collect();
+                return self.output.print(&records);
+            }
+            match newer {
+                true => println!(
+                    "nk {} is available, running {}. \
+                    Run `nk self-update` to install it.",
+                    version, current
+                ),
+                false => println!("nk {} is up to date", current),
+            }
+            return Ok(());
+        }
+        if version == current && !self.force {
+            if !self.output.is_text() {
+                let record =
+                    ActionRecord::new("nk", Some(current), Action::Unchanged);
+                return self.output.print(&[record]);
+            }
+            println!("nk {} is already running", current);
+            return Ok(());
+        }
+
+        // multicall links point to the real executable, which is replaced
+        let exe = env::current_exe()?.canonicalize()?;
+        let dir = exe
+            .parent()
+            .ok_or_else(|| format!("{} has no parent", exe.display()))?;
+        let staging = dir.join(format!(".nk-{}.update", version));
+        if staging.exists() {
+            fs::remove_dir_all(&staging).await?;
+        }
+        let result = async {
+            tool.install_into(&staging, &version).await?;
+            let new_exe = staging.join(tool.name());
+            verify(&new_exe).await?;
+            // staging is on the same filesystem, so this atomically replaces
+            // the executable, even while it is running
+            fs::rename(&new_exe, &exe).await?;
+            Ok::<_, crate::error::Error>(())
+        }
+        .await;
+        if staging.exists() {
+            fs::remove_dir_all(&staging).await?;
+        }
+        result.map_err(|e| {
+            format!("Failed to update {}: {}", exe.display(), e)
+        })?;
+
+        if !self.output.is_text() {
+            let mut record =
+                ActionRecord::new("nk", Some(version), Action::Updated);
+            record.path = Some(exe);
+            return self.output.print(&[record]);
+        }
+        println!("Updated nk from {} to {}", current, version);
+        Ok(())
+    }
+}
+
+/// the definition `nk self-update` installs nk with
+pub fn definition() -> Result<ToolDefinition> {
+    Ok(serde_yaml::from_str(DEFINITION)?)
+}
+
+/// makes sure the downloaded executable runs on this machine
+async fn verify(path: &std::path::Path) -> Result<()> {
+    let status = Command::new(path)
+        .arg("--help")
+        .env("NK_OFFLINE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status();
+    match tokio::time::timeout(VERIFY_TIMEOUT, status).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => {
+            Err(format!("new executable failed with {}", status).into())
+        }
+        Ok(Err(e)) => {
+            Err(format!("new executable failed to run: {}", e).into())
+        }
+        Err(_) => Err("new executable timed out".into()),
+    }
+}
@@ -2,7 +2,7 @@ use std::iter;
 
 use crate::result::Result;
 
-use crate::toolbox::tool::Tool;
+use crate::toolbox::tool::{parse_tool_spec, split_tool_spec, Tool};
 use crate::toolbox::Toolbox;
 
 pub struct ToolCommand<'a> {
@@ -13,7 +13,11 @@ pub struct ToolCommand<'a> {
 
 impl<'a> ToolCommand<'a> {
     pub async fn run(self) -> Result<()> {
-        let tool = self.toolbox.tool(&self.tool_name)?;
+        let (name, version_ref) = parse_tool_spec(&self.tool_name)?;
+        let mut tool = self.toolbox.tool_by_binary(name)?;
+        if let Some(version_ref) = version_ref {
+            tool = tool.with_version(vec![version_ref]);
+        }
 
         tool.run(self.args).await?;
         Ok(())
@@ -39,7 +43,10 @@ impl<'a> ToolCommand<'a> {
             Some(sc) => sc,
         };
 
-        if toolbox.tool(subcommand_name).is_ok() {
+        if toolbox
+            .tool_by_binary(split_tool_spec(subcommand_name).0)
+            .is_ok()
+        {
             return Ok(Self {
                 args: Tool::get_args(matches).map(String::from).collect(),
                 toolbox,
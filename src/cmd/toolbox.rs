@@ -1,13 +1,66 @@
+use crate::cmd::output::{Action, ActionRecord, OutputFormat, ToolRecord};
+use crate::cmd::{
+    bundle::Bundle, doctor::Doctor, link::Link, lint::Lint, outdated::Outdated,
+};
+use crate::lockfile::{LockedTool, Lockfile};
 use crate::result::Result;
-use crate::toolbox::tool::{Tool, VersionRef};
+use crate::toolbox::dependency;
+use crate::toolbox::platform::Platform;
+use crate::toolbox::tool::{
+    highest_in_range, parse_tool_spec, Tool, VersionRef,
+};
 use crate::toolbox::Toolbox;
 use clap::Parser;
+use futures::{stream, StreamExt};
+use indicatif::HumanBytes;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[clap(name = "toolbox", about = "manages the toolbox")]
 pub struct ToolboxCommand {
     #[clap(subcommand)]
     pub subcommand: Subcommand,
+    /// print json or yaml records instead of text
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        env = "NK_OUTPUT",
+        default_value = "text"
+    )]
+    pub output: OutputFormat,
+    /// install tools for another operating system, e.g. `darwin`. Tools of
+    /// other platforms are kept in their own directory.
+    #[clap(long, global = true)]
+    pub os: Option<String>,
+    /// install tools for another architecture, e.g. `arm64`
+    #[clap(long, global = true)]
+    pub arch: Option<String>,
+}
+
+/// describes `tool` and its installed versions
+async fn tool_record(tool: &Tool<'_>) -> Result<ToolRecord> {
+    let installed_versions = tool.find_local_versions().await?;
+    let active_version = tool.find_local_version().await?;
+    let path = match &active_version {
+        Some(version) => Some(
+            tool.toolbox
+                .exec_dir_path()?
+                .join(tool.name())
+                .join(version),
+        ),
+        None => None,
+    };
+    Ok(ToolRecord {
+        name: tool.name().to_string(),
+        description: tool.description().to_string(),
+        aliases: tool.definition.aliases().to_vec(),
+        installed_versions,
+        active_version,
+        latest_version: None,
+        available_versions: None,
+        path,
+    })
 }
 
 #[derive(Parser, Debug)]
@@ -17,16 +70,27 @@ pub struct List {
     tool: Option<String>,
 }
 impl List {
-    async fn run(&self, toolbox: &Toolbox) -> Result<()> {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
         if let Some(tool) = &self.tool {
-            self.list_versions(toolbox, tool).await
+            self.list_versions(toolbox, tool, output).await
         } else {
-            self.list(toolbox).await
+            self.list(toolbox, output).await
         }
     }
-    async fn list(&self, toolbox: &Toolbox) -> Result<()> {
+    async fn list(
+        &self,
+        toolbox: &Toolbox,
+        output: OutputFormat,
+    ) -> Result<()> {
         let repository = toolbox.repository();
         let tools = repository.tools();
+        if !output.is_text() {
+            let mut records = vec![];
+            for tool in tools {
+                records.push(tool_record(&Tool::new(tool, toolbox)).await?);
+            }
+            return output.print(&records);
+        }
         let length = tools.iter().map(|x| x.name().len()).max().unwrap_or(0);
 
         for tool in tools {
@@ -39,11 +103,23 @@ impl List {
         Ok(())
     }
 
-    async fn list_versions(&self, toolbox: &Toolbox, tool: &str) -> Result<()> {
+    async fn list_versions(
+        &self,
+        toolbox: &Toolbox,
+        tool: &str,
+        output: OutputFormat,
+    ) -> Result<()> {
         let tool = toolbox.tool(tool)?;
+        if !output.is_text() {
+            return output.print(&tool_record(&tool).await?);
+        }
         let versions = tool.find_local_versions().await?;
+        let active = tool.find_local_version().await?;
         for version in &versions {
-            println!("{}", version);
+            match Some(version) == active.as_ref() {
+                true => println!("{} (active)", version),
+                false => println!("{}", version),
+            }
         }
         if versions.is_empty() {
             eprintln!("No Versions Installed");
@@ -54,103 +130,631 @@ impl List {
 #[derive(Parser, Debug)]
 pub struct Remote {
     tool: String,
+    /// list all available versions instead of the latest one
+    #[clap(short, long, action)]
+    all: bool,
+    /// maximum number of versions to list
+    #[clap(short, long, requires = "all")]
+    limit: Option<usize>,
+    /// include prereleases
+    #[clap(short, long, action, requires = "all")]
+    prereleases: bool,
 }
 impl Remote {
-    async fn run(&self, toolbox: &Toolbox) -> Result<()> {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
         let tool = toolbox.tool(&self.tool)?;
+        let latest_version = tool.find_latest_version().await?;
+        let available_versions = match self.all {
+            true => Some(
+                tool.find_remote_versions(self.prereleases, self.limit)
+                    .await?,
+            ),
+            false => None,
+        };
 
-        println!("{}", tool.find_latest_version().await?);
+        let mut record = tool_record(&tool).await?;
+        if !output.is_text() {
+            record.latest_version = Some(latest_version);
+            record.available_versions = available_versions;
+            return output.print(&record);
+        }
+        let versions = match available_versions {
+            Some(versions) => versions,
+            None => vec![latest_version],
+        };
+        for version in versions {
+            if record.active_version.as_ref() == Some(&version) {
+                println!("{} (active)", version);
+            } else if record.installed_versions.contains(&version) {
+                println!("{} (installed)", version);
+            } else {
+                println!("{}", version);
+            }
+        }
         Ok(())
     }
 }
 
+/// installs `tools` with at most `jobs` of them at once. A failing tool
+/// does not stop the others. Tools that weren't installed yet are reported
+/// with the `changed` action.
+pub async fn install_all(
+    tools: &[Tool<'_>],
+    force: bool,
+    jobs: usize,
+    changed: Action,
+) -> Vec<ActionRecord> {
+    let mut results: Vec<ActionRecord> = stream::iter(tools)
+        .map(|tool| async move {
+            let (action, error) = match tool.install(force).await {
+                Ok(true) => (changed, None),
+                Ok(false) => (Action::Unchanged, None),
+                Err(e) => (Action::Failed, Some(e.to_string())),
+            };
+            let version = tool.resolve_version().await.ok();
+            let mut record = ActionRecord::new(tool.name(), version, action);
+            if error.is_none() {
+                record.path = tool.exec_dir_path().await.ok();
+            }
+            record.error = error;
+            record
+        })
+        .buffer_unordered(jobs.max(1))
+        .collect()
+        .await;
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    results
+}
+
+/// prints a table of `results` and fails if any of them failed
+pub fn print_summary(
+    results: &[ActionRecord],
+    unchanged: &str,
+    output: OutputFormat,
+) -> Result<()> {
+    let failed = results
+        .iter()
+        .filter(|r| r.action == Action::Failed)
+        .count();
+    if output.is_text() {
+        print_table(results, unchanged);
+    } else {
+        output.print(results)?;
+    }
+    if failed > 0 {
+        return Err(
+            format!("{} of {} tools failed", failed, results.len()).into()
+        );
+    }
+    Ok(())
+}
+
+fn print_table(results: &[ActionRecord], unchanged: &str) {
+    let name_width = results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    let version_width = results
+        .iter()
+        .map(|r| r.version.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0);
+    for result in results {
+        let status = match result.action {
+            Action::Unchanged => unchanged.to_string(),
+            Action::Failed => format!(
+                "failed: {}",
+                result.error.as_deref().unwrap_or_default()
+            ),
+            action => format!("{:?}", action).to_lowercase(),
+        };
+        println!(
+            "{:name_width$} {:version_width$} {}",
+            result.name,
+            result.version.as_deref().unwrap_or("-"),
+            status
+        );
+    }
+}
+
 #[derive(Parser, Debug)]
 pub struct Install {
-    tool: String,
+    #[clap(required = true)]
+    tools: Vec<String>,
     #[clap(short, long, action)]
     force: bool,
+    /// number of tools to install concurrently
+    #[clap(short, long, env = "NK_JOBS", default_value_t = 4)]
+    jobs: usize,
 }
 impl Install {
-    async fn run(&self, toolbox: &Toolbox) -> Result<()> {
-        let tool =
-            toolbox.tool_with_version(&self.tool, vec![VersionRef::Latest])?;
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let tools = self
+            .tools
+            .iter()
+            .map(|spec| {
+                let (name, version_ref) = parse_tool_spec(spec)?;
+                let version_ref = version_ref.unwrap_or(VersionRef::Latest);
+                toolbox.tool_with_version(name, vec![version_ref])
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        let name = tool.name().to_string();
-        match tool.install(self.force).await? {
-            true => println!("Installed: {}", name),
-            false => println!("Already installed: {}", name),
-        }
-        Ok(())
+        let results =
+            install_all(&tools, self.force, self.jobs, Action::Installed).await;
+        print_summary(&results, "already installed", output)
     }
 }
 
 #[derive(Parser, Debug)]
-pub struct Update {}
+pub struct Update {
+    /// number of tools to update concurrently
+    #[clap(short, long, env = "NK_JOBS", default_value_t = 4)]
+    jobs: usize,
+}
 impl Update {
-    async fn run(&self, toolbox: &Toolbox) -> Result<()> {
-        let tools = toolbox.installed_tools().await?;
-        for tool in tools {
-            let tool =
-                Tool::new_with_version(tool, toolbox, vec![VersionRef::Latest]);
-            let name = tool.name().to_string();
-            match tool.install(false).await? {
-                true => println!("Updated: {}", name),
-                false => println!("Already up to date: {}", name),
-            }
-        }
-        Ok(())
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let tools: Vec<_> = toolbox
+            .installed_tools()
+            .await?
+            .into_iter()
+            .map(|tool| {
+                Tool::new_with_version(tool, toolbox, vec![VersionRef::Latest])
+            })
+            .collect();
+
+        let results =
+            install_all(&tools, false, self.jobs, Action::Updated).await;
+        print_summary(&results, "up to date", output)
     }
 }
 
+/// removes old versions of installed tools. Active versions and versions
+/// locked or pinned by the current project are always kept.
 #[derive(Parser, Debug)]
-pub struct Cleanup {}
+pub struct Cleanup {
+    /// number of most recent versions to keep per tool
+    #[clap(short, long, default_value_t = 1)]
+    keep: usize,
+    /// also keep versions executed within this many days, even if they are
+    /// older than the ones kept by `--keep`. Combine with `--keep 0` to
+    /// only keep versions by their use.
+    #[clap(long)]
+    used_within: Option<u64>,
+    /// only print what would be removed
+    #[clap(long, action)]
+    dry_run: bool,
+}
 impl Cleanup {
-    async fn run(&self, toolbox: &Toolbox) -> Result<()> {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
         let tools = toolbox.installed_tools().await?;
+        let mut records = vec![];
         for tool in tools {
-            let tool =
-                Tool::new_with_version(tool, toolbox, vec![VersionRef::Latest]);
-            self.cleanup_tool(&tool).await?;
+            let tool = Tool::new(tool, toolbox);
+            records.extend(self.cleanup_tool(&tool, output).await?);
+        }
+        if !output.is_text() {
+            return output.print(&records);
+        }
+        let reclaimed = records.iter().filter_map(|r| r.size).sum();
+        match self.dry_run {
+            true => println!("Would reclaim {}", HumanBytes(reclaimed)),
+            false => println!("Reclaimed {}", HumanBytes(reclaimed)),
         }
         Ok(())
     }
 
-    async fn cleanup_tool(&self, tool: &Tool<'_>) -> Result<()> {
+    async fn cleanup_tool(
+        &self,
+        tool: &Tool<'_>,
+        output: OutputFormat,
+    ) -> Result<Vec<ActionRecord>> {
         let all_versions = tool.find_local_versions().await?;
-        let current_version = tool.find_local_version().await?;
-        for version in all_versions {
-            if Some(version.clone()) != current_version {
+        let mut kept_versions =
+            kept_versions(tool.toolbox, tool.name(), &all_versions);
+        kept_versions.extend(tool.find_active_version().await?);
+        let mut records = vec![];
+        for (newer, version) in all_versions.iter().rev().enumerate() {
+            if kept_versions.contains(version) {
                 continue;
             }
 
             let tool = Tool::new_with_version(
                 tool.definition,
                 tool.toolbox,
-                vec![VersionRef::Specific(version)],
+                vec![VersionRef::Specific(version.clone())],
             );
-            tool.remove().await?;
+            let unused = tool.last_used().await?.elapsed().unwrap_or_default();
+            if !is_expired(newer, unused, self.keep, self.used_within) {
+                continue;
+            }
+            let action = match self.dry_run {
+                true => Action::WouldRemove,
+                false => Action::Removed,
+            };
+            let mut record =
+                ActionRecord::new(tool.name(), Some(version.clone()), action);
+            record.size = Some(tool.disk_usage().await?);
+            record.path = Some(tool.exec_dir_path().await?);
+            if !self.dry_run {
+                tool.remove().await?;
+            }
+            if output.is_text() {
+                println!(
+                    "{}: {} {} ({})",
+                    if self.dry_run {
+                        "Would remove"
+                    } else {
+                        "Removed"
+                    },
+                    record.name,
+                    version,
+                    HumanBytes(record.size.unwrap_or_default())
+                );
+            }
+            records.push(record);
+        }
+        let reclaimed: u64 = records.iter().filter_map(|r| r.size).sum();
+        if reclaimed > 0 && output.is_text() {
+            println!("{}: {}", tool.name(), HumanBytes(reclaimed));
+        }
+        Ok(records)
+    }
+}
+
+/// whether `toolbox cleanup` removes a version that has `newer` installed
+/// versions newer than it and was executed `unused` ago. It is kept if it is
+/// one of the `keep` newest versions or, independently of that, was used
+/// within `used_within` days.
+pub fn is_expired(
+    newer: usize,
+    unused: Duration,
+    keep: usize,
+    used_within: Option<u64>,
+) -> bool {
+    let recently_used = matches!(
+        used_within,
+        Some(days) if unused < Duration::from_secs(days * 24 * 60 * 60)
+    );
+    newer >= keep && !recently_used
+}
+
+/// versions of `name` that are locked or pinned by the current project
+fn kept_versions(
+    toolbox: &Toolbox,
+    name: &str,
+    local_versions: &[String],
+) -> Vec<String> {
+    let mut versions = vec![];
+    if let Some(locked) = toolbox.lockfile().and_then(|l| l.tool(name)) {
+        versions.push(locked.version().to_string());
+    }
+    match toolbox.project().and_then(|p| p.pinned_version_ref(name)) {
+        Some(VersionRef::Specific(version)) => versions.push(version),
+        Some(VersionRef::Range(range)) => {
+            versions.extend(highest_in_range(local_versions.to_vec(), &range))
+        }
+        _ => {}
+    }
+    versions
+}
+
+/// selects the version of a tool that is used unless a project pins one
+#[derive(Parser, Debug)]
+pub struct Use {
+    tool: String,
+    #[clap(required_unless_present = "latest")]
+    version: Option<String>,
+    /// go back to using the highest installed version
+    #[clap(long, action, conflicts_with = "version")]
+    latest: bool,
+}
+impl Use {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let tool = toolbox.tool(&self.tool)?;
+        let name = tool.name().to_string();
+        let version = match &self.version {
+            Some(version) => {
+                let tool = tool.with_version(vec![version.parse()?]);
+                tool.install(false).await?;
+                let version = tool.resolve_version().await?;
+                tool.set_active_version(Some(&version)).await?;
+                Some(version)
+            }
+            None => {
+                tool.set_active_version(None).await?;
+                tool.find_local_version().await?
+            }
+        };
+
+        if !output.is_text() {
+            let mut records = vec![];
+            if let Some(version) = version {
+                let mut record = ActionRecord::new(
+                    &name,
+                    Some(version.clone()),
+                    Action::Activated,
+                );
+                record.path =
+                    Some(toolbox.exec_dir_path()?.join(&name).join(version));
+                records.push(record);
+            }
+            return output.print(&records);
+        }
+        if let Some(version) = version {
+            println!("Using: {} {}", name, version);
         }
         Ok(())
     }
 }
 
+/// removes all versions of tools
+#[derive(Parser, Debug)]
+pub struct Uninstall {
+    #[clap(required = true)]
+    tools: Vec<String>,
+}
+impl Uninstall {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let mut records = vec![];
+        for name in &self.tools {
+            let tool = toolbox.tool(name)?;
+            let versions = tool.find_local_versions().await?;
+            if versions.is_empty() {
+                match output.is_text() {
+                    true => println!("Not installed: {}", name),
+                    false => records.push(ActionRecord::new(
+                        name,
+                        None,
+                        Action::NotInstalled,
+                    )),
+                }
+                continue;
+            }
+            let mut reclaimed = 0;
+            for version in versions {
+                let tool = Tool::new_with_version(
+                    tool.definition,
+                    toolbox,
+                    vec![VersionRef::Specific(version.clone())],
+                );
+                let mut record =
+                    ActionRecord::new(name, Some(version), Action::Removed);
+                record.size = Some(tool.disk_usage().await?);
+                record.path = Some(tool.exec_dir_path().await?);
+                reclaimed += record.size.unwrap_or_default();
+                tool.remove().await?;
+                records.push(record);
+            }
+            let tool_dir = toolbox.exec_dir_path()?.join(tool.name());
+            if tool_dir.exists() {
+                tokio::fs::remove_dir_all(&tool_dir).await?;
+            }
+            if output.is_text() {
+                println!("Uninstalled: {} ({})", name, HumanBytes(reclaimed));
+            }
+        }
+        output.print(&records)
+    }
+}
+
+/// writes the exact versions of all tools the project uses to the lockfile
+#[derive(Parser, Debug)]
+pub struct Lock {}
+impl Lock {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let project = toolbox.required_project()?;
+
+        let mut tools: Vec<(String, Vec<VersionRef>)> = project
+            .pinned_tools()
+            .map(|(name, _)| {
+                (name.to_string(), toolbox.pinned_version_refs(name))
+            })
+            .collect();
+        let pinned: Vec<_> =
+            project.pinned_tools().map(|(name, _)| name).collect();
+        for name in pinned {
+            for dep in dependency::resolve(toolbox.repository().tools(), name)?
+            {
+                let (dep, version_ref) = parse_tool_spec(dep)?;
+                if tools.iter().any(|(name, _)| name == dep) {
+                    continue;
+                }
+                let version_refs = match version_ref {
+                    Some(version_ref)
+                        if project.pinned_version(dep).is_none() =>
+                    {
+                        vec![version_ref]
+                    }
+                    _ => toolbox.pinned_version_refs(dep),
+                };
+                tools.push((dep.to_string(), version_refs));
+            }
+        }
+
+        let mut lockfile = Lockfile::default();
+        let mut records = vec![];
+        for (name, version_refs) in tools {
+            let tool = toolbox.tool_with_version(&name, version_refs)?;
+            tool.install(false).await?;
+            let version = tool.resolve_version().await?;
+            let url =
+                tool.definition.package_url(&version, &tool.platform())?;
+            let sha256 = tool.digest().await?;
+            match output.is_text() {
+                true => println!("Locked: {} {}", name, version),
+                false => {
+                    let mut record = ActionRecord::new(
+                        &name,
+                        Some(version.clone()),
+                        Action::Locked,
+                    );
+                    record.path = Some(tool.exec_dir_path().await?);
+                    records.push(record);
+                }
+            }
+            lockfile.insert(&name, LockedTool::new(version, url, sha256));
+        }
+        lockfile.save(&project.lockfile_path())?;
+        output.print(&records)
+    }
+}
+
+/// installs exactly the tools recorded in the lockfile
+#[derive(Parser, Debug)]
+pub struct Sync {}
+impl Sync {
+    async fn run(&self, toolbox: &Toolbox, output: OutputFormat) -> Result<()> {
+        let project = toolbox.required_project()?;
+        let lockfile = toolbox.lockfile().ok_or_else(|| {
+            format!(
+                "No lockfile found at {}, run `nk toolbox lock` first",
+                project.lockfile_path().display()
+            )
+        })?;
+
+        for (name, pinned) in project.pinned_tools() {
+            let locked = lockfile.tool(name).ok_or_else(|| {
+                format!(
+                    "{} is not in the lockfile, run `nk toolbox lock`",
+                    name
+                )
+            })?;
+            if !satisfies(pinned, locked.version()) {
+                return Err(format!(
+                    "{} is pinned to {} but locked to {}, run `nk toolbox lock`",
+                    name,
+                    pinned,
+                    locked.version()
+                )
+                .into());
+            }
+        }
+
+        let mut records = vec![];
+        for (name, locked) in lockfile.tools() {
+            let tool = toolbox.tool_with_version(
+                name,
+                vec![VersionRef::Specific(locked.version().to_string())],
+            )?;
+            let url = tool
+                .definition
+                .package_url(locked.version(), &tool.platform())?;
+            if url != locked.url() {
+                return Err(format!(
+                    "Package url of {} changed from {} to {}",
+                    name,
+                    locked.url(),
+                    url
+                )
+                .into());
+            }
+            let installed = tool.install(false).await?;
+            let sha256 = tool.digest().await?;
+            if sha256 != locked.sha256() {
+                // never leave a binary behind that the lockfile rejects
+                tool.remove().await?;
+                return Err(format!(
+                    "{} {} does not match the lockfile: expected sha256 {}, got {}",
+                    name,
+                    locked.version(),
+                    locked.sha256(),
+                    sha256
+                )
+                .into());
+            }
+            if output.is_text() {
+                println!("Synced: {} {}", name, locked.version());
+                continue;
+            }
+            let action = match installed {
+                true => Action::Installed,
+                false => Action::Unchanged,
+            };
+            let mut record = ActionRecord::new(
+                name,
+                Some(locked.version().to_string()),
+                action,
+            );
+            record.path = Some(tool.exec_dir_path().await?);
+            records.push(record);
+        }
+        output.print(&records)
+    }
+}
+
+/// whether the `locked` version still satisfies the version `pinned` by the
+/// project. Pins that depend on the environment, like `cluster`, always do.
+fn satisfies(pinned: &str, locked: &str) -> bool {
+    match pinned.parse() {
+        Ok(VersionRef::Specific(version)) => version == locked,
+        Ok(VersionRef::Range(range)) => {
+            highest_in_range(vec![locked.to_string()], &range).is_some()
+        }
+        Ok(_) => true,
+        Err(_) => false,
+    }
+}
+
 #[derive(Parser, Debug)]
 pub enum Subcommand {
     List(List),
     Update(Update),
     Remote(Remote),
+    Outdated(Outdated),
     Install(Install),
     Cleanup(Cleanup),
+    Uninstall(Uninstall),
+    Doctor(Doctor),
+    Link(Link),
+    Lint(Lint),
+    Bundle(Bundle),
+    Use(Use),
+    Lock(Lock),
+    Sync(Sync),
 }
 
 impl ToolboxCommand {
     pub async fn run(self, toolbox: &Toolbox) -> Result<()> {
+        let foreign;
+        let toolbox = if self.os.is_some() || self.arch.is_some() {
+            let current = Platform::current();
+            let platform = Platform::parse(
+                self.os.as_deref().unwrap_or_else(|| current.os()),
+                self.arch.as_deref().unwrap_or_else(|| current.arch()),
+            )?;
+            foreign = toolbox.with_platform(platform).await?;
+            &foreign
+        } else {
+            toolbox
+        };
         match &self.subcommand {
-            Subcommand::List(list) => list.run(toolbox).await,
-            Subcommand::Remote(remote) => remote.run(toolbox).await,
-            Subcommand::Update(update) => update.run(toolbox).await,
-            Subcommand::Install(install) => install.run(toolbox).await,
-            Subcommand::Cleanup(cleanup) => cleanup.run(toolbox).await,
+            Subcommand::List(list) => list.run(toolbox, self.output).await,
+            Subcommand::Remote(remote) => {
+                remote.run(toolbox, self.output).await
+            }
+            Subcommand::Outdated(outdated) => {
+                outdated.run(toolbox, self.output).await
+            }
+            Subcommand::Update(update) => {
+                update.run(toolbox, self.output).await
+            }
+            Subcommand::Install(install) => {
+                install.run(toolbox, self.output).await
+            }
+            Subcommand::Cleanup(cleanup) => {
+                cleanup.run(toolbox, self.output).await
+            }
+            Subcommand::Uninstall(uninstall) => {
+                uninstall.run(toolbox, self.output).await
+            }
+            Subcommand::Doctor(doctor) => {
+                doctor.run(toolbox, self.output).await
+            }
+            Subcommand::Link(link) => link.run(toolbox, self.output).await,
+            Subcommand::Lint(lint) => lint.run(toolbox, self.output).await,
+            Subcommand::Bundle(bundle) => {
+                bundle.run(toolbox, self.output).await
+            }
+            Subcommand::Use(r#use) => r#use.run(toolbox, self.output).await,
+            Subcommand::Lock(lock) => lock.run(toolbox, self.output).await,
+            Subcommand::Sync(sync) => sync.run(toolbox, self.output).await,
         }
     }
 }
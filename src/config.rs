@@ -0,0 +1,144 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use serde::Deserialize;
+
+use crate::{dirs::Dirs, result::Result};
+
+pub static CONFIG_FILE_NAME: &str = "config.yaml";
+
+/// user configuration, read from `config.yaml` in the config dir
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct Config {
+    /// proxy used for all requests
+    #[serde(default)]
+    proxy: Option<String>,
+    /// additional PEM encoded CA bundles to trust
+    #[serde(default)]
+    ca_certificates: Vec<PathBuf>,
+    /// rewrites url prefixes, e.g. to route downloads through a mirror
+    #[serde(default)]
+    rewrites: Vec<RewriteRule>,
+    /// package url templates per tool that are tried in order if the
+    /// upstream package url fails
+    #[serde(default)]
+    mirrors: HashMap<String, Vec<String>>,
+    /// opts into a notice when installed tools fall behind upstream
+    #[serde(default)]
+    update_notice: Option<UpdateNotice>,
+    /// overrides the environment variables and arguments of tools
+    #[serde(default)]
+    tools: HashMap<String, ToolSettings>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ToolSettings {
+    /// added to the environment variables of the definition, replacing
+    /// the ones with the same name
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// replaces the arguments of the definition if set
+    #[serde(default)]
+    args: Option<Vec<String>>,
+}
+
+impl ToolSettings {
+    pub fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+
+    pub fn args(&self) -> Option<&[String]> {
+        self.args.as_deref()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct UpdateNotice {
+    /// number of minor versions a tool may fall behind without a notice
+    #[serde(default = "default_minor_versions")]
+    minor_versions: u64,
+    /// hours between two checks and between two notices
+    #[serde(default = "default_interval_hours")]
+    interval_hours: u64,
+}
+
+fn default_minor_versions() -> u64 {
+    2
+}
+
+fn default_interval_hours() -> u64 {
+    24
+}
+
+impl UpdateNotice {
+    pub fn minor_versions(&self) -> u64 {
+        self.minor_versions
+    }
+
+    pub fn interval_hours(&self) -> u64 {
+        self.interval_hours
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct RewriteRule {
+    prefix: String,
+    replacement: String,
+}
+
+impl RewriteRule {
+    /// rewrites `url` if it starts with the prefix of this rule
+    pub fn apply(&self, url: &str) -> Option<String> {
+        url.strip_prefix(&self.prefix)
+            .map(|rest| format!("{}{}", self.replacement, rest))
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let path = Dirs::config_dir()?.join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read(&path)?;
+        Ok(Self::from_slice(&content)
+            .map_err(|e| format!("{}: {}", path.display(), e))?)
+    }
+
+    pub fn from_slice(content: &[u8]) -> Result<Self> {
+        Ok(serde_yaml::from_slice(content)?)
+    }
+
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    pub fn ca_certificates(&self) -> &[PathBuf] {
+        &self.ca_certificates
+    }
+
+    pub fn rewrites(&self) -> &[RewriteRule] {
+        &self.rewrites
+    }
+
+    pub fn mirrors(&self, tool: &str) -> &[String] {
+        self.mirrors
+            .get(tool)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn update_notice(&self) -> Option<&UpdateNotice> {
+        self.update_notice.as_ref()
+    }
+
+    pub fn tool_settings(&self, tool: &str) -> Option<&ToolSettings> {
+        self.tools.get(tool)
+    }
+}
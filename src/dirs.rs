@@ -11,6 +11,15 @@ impl Dirs {
             _ => Ok(Self::dirs()?.data_dir().to_path_buf()),
         }
     }
+    pub fn config_dir() -> Result<PathBuf> {
+        match std::env::var("NK_CONFIG_DIR") {
+            Ok(path) => Ok(PathBuf::from(path)),
+            _ => Ok(Self::dirs()?.config_dir().to_path_buf()),
+        }
+    }
+    pub fn cache_dir() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("cache"))
+    }
     fn dirs() -> Result<directories::ProjectDirs> {
         directories::ProjectDirs::from("dev", "withlazers", "neatkube")
             .ok_or_else(|| "Failed to get project dirs".into())
@@ -1,51 +1,380 @@
 use bytes::Bytes;
+use futures::future::BoxFuture;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use reqwest::Client;
-use tokio::fs::{self, File};
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    Certificate, Client, Method, Proxy, RequestBuilder, Response, StatusCode,
+    Url,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs::{self, File, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use tokio_stream::{Stream, StreamExt};
 
+use crate::config::{Config, RewriteRule};
+use crate::dirs::Dirs;
 use crate::result::Result;
-use std::{path::Path, pin::Pin};
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 type DownloadStream = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>;
 
+static DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// rate limits resetting later than this are reported instead of waited for
+static MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+static MAX_RATE_LIMIT_RETRIES: usize = 3;
+static MAX_DOWNLOAD_ATTEMPTS: usize = 5;
+static INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+static PEM_END: &str = "-----END CERTIFICATE-----";
+static GITHUB_HOSTS: &[&str] = &["github.com", "api.github.com"];
+
+/// receives the body of a download while it is written to disk
+pub trait DownloadSink {
+    /// a response starting at `offset` arrived. The bytes before it are
+    /// the first `offset` bytes of the file at `path`.
+    fn restart<'a>(
+        &'a mut self,
+        path: &'a Path,
+        offset: u64,
+    ) -> BoxFuture<'a, Result<()>>;
+    /// the next chunk of the response, may wait for a slow sink
+    fn write<'a>(&'a mut self, chunk: &'a [u8]) -> BoxFuture<'a, ()>;
+}
+
+impl DownloadSink for () {
+    fn restart<'a>(
+        &'a mut self,
+        _path: &'a Path,
+        _offset: u64,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+    fn write<'a>(&'a mut self, _chunk: &'a [u8]) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+}
+
 pub struct Downloader {
     client: Client,
     progress: MultiProgress,
+    offline: AtomicBool,
+    cache_ttl: Duration,
+    rewrites: Vec<RewriteRule>,
+}
+
+/// a request that failed before a response arrived. Only these are worth
+/// retrying.
+#[derive(Debug)]
+struct TransportError(String);
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+struct CachedResponse {
+    etag: Option<String>,
+    fetched_at: u64,
+    body: String,
+}
+
+/// splits a PEM bundle into its certificates, as native-tls only reads the
+/// first certificate of a PEM file
+pub fn split_certificates(bundle: &str) -> Vec<&str> {
+    bundle
+        .split_inclusive(PEM_END)
+        .filter(|pem| pem.contains(PEM_END))
+        .collect()
 }
 
 impl Default for Downloader {
     fn default() -> Self {
-        // Reqwest setup
-        let progress = MultiProgress::new();
-        let client = Client::builder()
-            .user_agent(concat!(
-                env!("CARGO_PKG_NAME"),
-                "-",
-                env!("CARGO_PKG_VERSION")
-            ))
-            .build()
-            .unwrap();
-        Self { client, progress }
+        Self::new(&Config::default()).unwrap()
     }
 }
 
 impl Downloader {
-    async fn download_to_raw(
-        &self,
-        stream: DownloadStream,
-        file: File,
-    ) -> Result<()> {
-        let mut file = file;
-        let mut stream = stream;
+    pub fn new(config: &Config) -> Result<Self> {
+        // Reqwest setup
+        let progress = MultiProgress::new();
+        let mut builder = Client::builder().user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "-",
+            env!("CARGO_PKG_VERSION")
+        ));
+        if let Some(proxy) = config.proxy() {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        for path in config.ca_certificates() {
+            let bundle = std::fs::read_to_string(path)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            for pem in split_certificates(&bundle) {
+                builder = builder.add_root_certificate(Certificate::from_pem(
+                    pem.as_bytes(),
+                )?);
+            }
+        }
+        let client = builder.build()?;
+        let offline = env::var("NK_OFFLINE")
+            .map(|v| !matches!(v.as_str(), "" | "0" | "false"))
+            .unwrap_or(false);
+        let cache_ttl = env::var("NK_CACHE_TTL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_CACHE_TTL);
+        Ok(Self {
+            client,
+            progress,
+            offline: AtomicBool::new(offline),
+            cache_ttl,
+            rewrites: config.rewrites().to_vec(),
+        })
+    }
 
-        while let Some(chunk) = stream.next().await {
-            file.write_all(&chunk?).await?;
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed)
+    }
+
+    fn check_online(&self, url: &str) -> Result<()> {
+        if self.is_offline() {
+            return Err(format!(
+                "Offline mode: refusing to download '{}'. Unset NK_OFFLINE \
+                or drop --offline to allow network access",
+                url
+            )
+            .into());
         }
         Ok(())
     }
 
+    fn github_token() -> Option<String> {
+        ["GITHUB_TOKEN", "GH_TOKEN"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|token| !token.is_empty())
+    }
+
+    /// applies the first matching rewrite rule to `url`
+    fn rewrite(&self, url: &str) -> String {
+        self.rewrites
+            .iter()
+            .find_map(|rule| rule.apply(url))
+            .unwrap_or_else(|| url.to_string())
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        let is_github = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|h| GITHUB_HOSTS.contains(&h)))
+            .unwrap_or(false);
+        match Self::github_token() {
+            Some(token) if is_github => request.bearer_auth(token),
+            _ => request,
+        }
+    }
+
+    /// whether `res` reports a rate limit and the time until it resets, if
+    /// the response tells
+    fn rate_limit_wait(res: &Response) -> Option<Option<Duration>> {
+        let headers = res.headers();
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+        };
+        let exhausted = header("x-ratelimit-remaining") == Some(0);
+        let retry_after = header(header::RETRY_AFTER.as_str());
+        match res.status() {
+            StatusCode::TOO_MANY_REQUESTS => {}
+            StatusCode::FORBIDDEN if exhausted || retry_after.is_some() => {}
+            _ => return None,
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let secs = retry_after.or_else(|| {
+            header("x-ratelimit-reset").map(|r| r.saturating_sub(now))
+        });
+        Some(secs.map(Duration::from_secs))
+    }
+
+    /// sends a GET request, waiting for short rate limits to reset
+    async fn send(&self, url: &str, headers: HeaderMap) -> Result<Response> {
+        self.send_request(Method::GET, url, headers).await
+    }
+
+    /// like `send`, but with any method
+    async fn send_request(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<Response> {
+        self.check_online(url)?;
+        let url = &self.rewrite(url);
+        let mut retries = 0;
+        loop {
+            let res = self
+                .request(method.clone(), url)
+                .headers(headers.clone())
+                .send()
+                .await
+                .map_err(|e| {
+                    TransportError(format!(
+                        "Failed to {} from '{}': {}",
+                        method, &url, e
+                    ))
+                })?;
+            let wait = match Self::rate_limit_wait(&res) {
+                None => return Ok(res),
+                Some(wait) => wait,
+            };
+
+            // without a reset time there is nothing sensible to wait for
+            let wait = match wait {
+                Some(wait)
+                    if wait <= MAX_RATE_LIMIT_WAIT
+                        && retries < MAX_RATE_LIMIT_RETRIES =>
+                {
+                    wait
+                }
+                _ => {
+                    let hint = match Self::github_token() {
+                        Some(_) => {
+                            "The limit of the configured token is exhausted."
+                        }
+                        None => {
+                            "Set GITHUB_TOKEN or GH_TOKEN to raise the limit."
+                        }
+                    };
+                    let resets_in = match wait.map(|wait| wait.as_secs()) {
+                        Some(secs) if secs < 60 => {
+                            format!(", resets in {}s", secs)
+                        }
+                        Some(secs) => {
+                            format!(", resets in {} minutes", secs / 60)
+                        }
+                        None => String::new(),
+                    };
+                    return Err(format!(
+                        "Rate limit exceeded for '{}'{}. {}",
+                        url, resets_in, hint
+                    )
+                    .into());
+                }
+            };
+            self.progress.println(format!(
+                "Rate limited by '{}', retrying in {}s",
+                url,
+                wait.as_secs()
+            ))?;
+            tokio::time::sleep(wait).await;
+            retries += 1;
+        }
+    }
+
+    /// status code `url` responds with to a HEAD request. Servers that
+    /// don't allow HEAD are asked for the first byte only.
+    pub async fn status(&self, url: &str) -> Result<StatusCode> {
+        let res = self
+            .send_request(Method::HEAD, url, HeaderMap::new())
+            .await?;
+        if res.status() != StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(res.status());
+        }
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=0-0"));
+        match self.send(url, headers).await?.status() {
+            // empty files can't satisfy the range, but exist
+            StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE => {
+                Ok(StatusCode::OK)
+            }
+            status => Ok(status),
+        }
+    }
+
+    fn cache_path(url: &str) -> Result<PathBuf> {
+        let digest: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok(Dirs::cache_dir()?.join("http").join(digest + ".yaml"))
+    }
+
+    /// like `string`, but answers from an on-disk cache while it is younger
+    /// than the cache ttl and revalidates it using the ETag afterwards. In
+    /// offline mode cached responses are used regardless of their age.
+    pub async fn cached_string(&self, url: &str) -> Result<String> {
+        let cache_path = Self::cache_path(url)?;
+        let cached: Option<CachedResponse> = match fs::read(&cache_path).await {
+            Ok(content) => serde_yaml::from_slice(&content).ok(),
+            Err(_) => None,
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        if let Some(cached) = &cached {
+            let age = now.saturating_sub(cached.fetched_at);
+            if self.is_offline() || age < self.cache_ttl.as_secs() {
+                return Ok(cached.body.clone());
+            }
+        }
+
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = cached.as_ref().and_then(|c| c.etag.as_ref()) {
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(etag)?);
+        }
+        let res = self.send(url, headers).await?;
+
+        let response = match (res.status(), cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => CachedResponse {
+                fetched_at: now,
+                ..cached
+            },
+            (status, _) if status.is_success() => CachedResponse {
+                etag: res
+                    .headers()
+                    .get(header::ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(String::from),
+                fetched_at: now,
+                body: res.text().await?,
+            },
+            (status, _) => {
+                return Err(format!(
+                    "Failed to GET from '{}': {}: {}",
+                    url,
+                    status,
+                    res.text().await.unwrap_or_default().trim()
+                )
+                .into());
+            }
+        };
+
+        fs::create_dir_all(cache_path.parent().unwrap()).await?;
+        fs::write(&cache_path, serde_yaml::to_string(&response)?).await?;
+        Ok(response.body)
+    }
+
     pub async fn string(&self, url: &str, msg: &str) -> Result<String> {
         let mut stream = self.stream(url, msg).await?;
 
@@ -57,31 +386,231 @@ impl Downloader {
     }
 
     pub async fn file(&self, url: &str, path: &Path, msg: &str) -> Result<()> {
-        let stream = self.stream(url, msg).await?;
         let temp_path = path.with_extension("part");
         fs::create_dir_all(path.parent().unwrap()).await?;
-        let file = File::create(&temp_path).await?;
-        match self.download_to_raw(stream, file).await {
-            Ok(_) => {
-                fs::rename(&temp_path, path).await?;
-                Ok(())
+        self.resume(url, &temp_path, msg).await?;
+        fs::rename(&temp_path, path).await?;
+        Ok(())
+    }
+
+    /// downloads the first of `urls` that can be fetched to `path` and
+    /// returns the url used. Each url is downloaded to its own partial
+    /// file, so a download is only ever resumed from the server it was
+    /// started with.
+    pub async fn resume_any(
+        &self,
+        urls: &[String],
+        path: &Path,
+        msg: &str,
+    ) -> Result<String> {
+        self.resume_any_into(urls, path, msg, &mut ()).await
+    }
+
+    /// like `resume_any`, but also passes the download to `sink`
+    pub async fn resume_any_into(
+        &self,
+        urls: &[String],
+        path: &Path,
+        msg: &str,
+        sink: &mut (dyn DownloadSink + Send),
+    ) -> Result<String> {
+        let mut errors = vec![];
+        for url in urls {
+            let part_path = Self::part_path(path, url);
+            match self.resume_into(url, &part_path, msg, sink).await {
+                Ok(_) => {
+                    fs::rename(&part_path, path).await?;
+                    for url in urls {
+                        let part_path = Self::part_path(path, url);
+                        if part_path.exists() {
+                            fs::remove_file(&part_path).await?;
+                        }
+                    }
+                    return Ok(url.clone());
+                }
+                Err(e) => errors.push(e.to_string()),
             }
-            Err(e) => {
-                fs::remove_file(&temp_path).await?;
-                Err(e)
+        }
+        Err(errors.join("\n").into())
+    }
+
+    /// partial file `url` is downloaded to by `resume_any`, e.g.
+    /// `helm.pkg.1a2b3c4d.part` for `helm.pkg`
+    pub fn part_path(path: &Path, url: &str) -> PathBuf {
+        let digest: String = Sha256::digest(url.as_bytes())[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let mut file_name = path.file_name().unwrap_or_default().to_owned();
+        file_name.push(format!(".{}.part", digest));
+        path.with_file_name(file_name)
+    }
+
+    /// downloads `url` to `path`. Interrupted downloads are retried with an
+    /// exponential backoff. Data already in `path` is kept and only the
+    /// remainder is requested if the server supports range requests.
+    pub async fn resume(
+        &self,
+        url: &str,
+        path: &Path,
+        msg: &str,
+    ) -> Result<()> {
+        self.resume_into(url, path, msg, &mut ()).await
+    }
+
+    /// like `resume`, but also passes the download to `sink`
+    pub async fn resume_into(
+        &self,
+        url: &str,
+        path: &Path,
+        msg: &str,
+        sink: &mut (dyn DownloadSink + Send),
+    ) -> Result<()> {
+        self.check_online(url)?;
+        let pb = self.progress_bar(msg, 0)?;
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1.. {
+            let reason = match self.resume_attempt(url, path, &pb, sink).await?
+            {
+                None => break,
+                Some(reason) => reason,
+            };
+            if attempt >= MAX_DOWNLOAD_ATTEMPTS {
+                return Err(format!(
+                    "Failed to download '{}' after {} attempts: {}",
+                    url, attempt, reason
+                )
+                .into());
             }
+            self.progress.println(format!(
+                "{}, retrying in {}s",
+                reason,
+                backoff.as_secs()
+            ))?;
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
         }
+        pb.finish();
+        Ok(())
     }
 
-    pub async fn stream(&self, url: &str, msg: &str) -> Result<DownloadStream> {
-        let res = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .map_err(|_| format!("Failed to GET from '{}'", &url))?;
-        let total_size = res.content_length().unwrap_or(0);
+    /// a single attempt of `resume`. Returns the reason if the download was
+    /// interrupted and is worth retrying.
+    async fn resume_attempt(
+        &self,
+        url: &str,
+        path: &Path,
+        pb: &ProgressBar,
+        sink: &mut (dyn DownloadSink + Send),
+    ) -> Result<Option<String>> {
+        let offset = match fs::metadata(path).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let mut headers = HeaderMap::new();
+        if offset > 0 {
+            headers.insert(
+                header::RANGE,
+                HeaderValue::from_str(&format!("bytes={}-", offset))?,
+            );
+        }
+        let res = match self.send(url, headers).await {
+            Ok(res) => res,
+            Err(e) if e.is::<TransportError>() => {
+                return Ok(Some(e.to_string()))
+            }
+            Err(e) => return Err(e),
+        };
 
+        let status = res.status();
+        let content_range = res
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_content_range);
+        let mut file = match status {
+            StatusCode::PARTIAL_CONTENT
+                if content_range.map(|(start, _)| start) == Some(offset) =>
+            {
+                OpenOptions::new().append(true).open(path).await?
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE
+                if content_range.and_then(|(_, total)| total)
+                    == Some(offset) =>
+            {
+                // already complete
+                sink.restart(path, offset).await?;
+                return Ok(None);
+            }
+            StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE => {
+                // the partial file doesn't match the resource anymore
+                fs::remove_file(path).await?;
+                return Ok(Some(format!(
+                    "'{}' responded with {}, restarting download",
+                    url, status
+                )));
+            }
+            status if status.is_success() => {
+                if offset > 0 {
+                    self.progress.println(format!(
+                        "'{}' does not support resuming, restarting download",
+                        url
+                    ))?;
+                }
+                File::create(path).await?
+            }
+            status
+                if status.is_server_error()
+                    || status == StatusCode::REQUEST_TIMEOUT =>
+            {
+                return Ok(Some(format!(
+                    "'{}' responded with {}",
+                    url, status
+                )));
+            }
+            status => {
+                // the partial file can't be resumed from this url
+                if offset > 0 {
+                    fs::remove_file(path).await?;
+                }
+                return Err(format!(
+                    "Failed to download '{}': {}",
+                    url, status
+                )
+                .into());
+            }
+        };
+
+        let start = match status {
+            StatusCode::PARTIAL_CONTENT => offset,
+            _ => 0,
+        };
+        sink.restart(path, start).await?;
+        pb.set_length(start + res.content_length().unwrap_or(0));
+        pb.set_position(start);
+        let mut received = start;
+        let mut stream = res.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    file.flush().await?;
+                    return Ok(Some(format!(
+                        "Download of '{}' ({}) interrupted after {} bytes: {}",
+                        url, status, received, e
+                    )));
+                }
+            };
+            file.write_all(&chunk).await?;
+            sink.write(&chunk).await;
+            received += chunk.len() as u64;
+            pb.inc(chunk.len() as u64);
+        }
+        file.flush().await?;
+        Ok(None)
+    }
+
+    fn progress_bar(&self, msg: &str, total_size: u64) -> Result<ProgressBar> {
         // Indicatif setup
         let pb = ProgressBar::new(total_size);
         let ps =ProgressStyle::default_bar()
@@ -91,12 +620,31 @@ impl Downloader {
         pb.set_style(ps);
         pb.set_message(msg.to_string());
 
-        let pb = self.progress.add(pb);
+        Ok(self.progress.add(pb))
+    }
+
+    pub async fn stream(&self, url: &str, msg: &str) -> Result<DownloadStream> {
+        let res = self.send(url, HeaderMap::new()).await?;
+        let status = res.status();
+        if !status.is_success() {
+            return Err(
+                format!("Failed to GET from '{}': {}", url, status).into()
+            );
+        }
+        let pb = self.progress_bar(msg, res.content_length().unwrap_or(0))?;
+        let url = url.to_string();
 
         // download chunks
         let stream = res.bytes_stream().map(move |chunk| {
-            let chunk = chunk
-                .map_err(|_| "Error while downloading file".to_string())?;
+            let chunk = chunk.map_err(|e| {
+                format!(
+                    "Download of '{}' ({}) interrupted after {} bytes: {}",
+                    url,
+                    status,
+                    pb.position(),
+                    e
+                )
+            })?;
             pb.inc(chunk.len() as u64);
             Ok(chunk)
         });
@@ -104,3 +652,14 @@ impl Downloader {
         Ok(Box::pin(stream))
     }
 }
+
+/// parses the start and total size of a `Content-Range` header like
+/// `bytes 100-199/200` or `bytes */200`
+pub fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = match range.split_once('-') {
+        Some((start, _)) => start.parse().ok()?,
+        None => 0,
+    };
+    Some((start, total.parse().ok()))
+}
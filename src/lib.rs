@@ -1,9 +1,14 @@
+pub mod bundle;
+pub mod cluster;
 pub mod cmd;
 pub mod completion;
+pub mod config;
 pub mod dirs;
 pub mod download;
 pub mod error;
+pub mod lockfile;
 pub mod podutil;
+pub mod project;
 pub mod result;
 pub mod toolbox;
 
@@ -11,19 +16,22 @@ use std::env;
 use std::ffi::OsStr;
 use std::path::Path;
 
+use clap::Arg;
 use clap::Command;
 use clap::FromArgMatches;
 use clap::IntoApp;
 use cmd::cfg_pack::CfgPackCommand;
 use cmd::completion::CompletionCommand;
 use cmd::copy::CopyCommand;
+use cmd::self_update::SelfUpdateCommand;
 use cmd::shell::ShellCommand;
 use cmd::tool::ToolCommand;
 use cmd::toolbox::ToolboxCommand;
+use toolbox::platform::Platform;
 use toolbox::Toolbox;
 
 pub async fn run() -> Result<(), error::Error> {
-    let toolbox = Toolbox::create().await?;
+    let toolbox = Toolbox::create(Platform::current()).await?;
 
     let mut args = env::args();
     let arg0_tool = args
@@ -32,9 +40,10 @@ pub async fn run() -> Result<(), error::Error> {
         .map(Path::new)
         .and_then(Path::file_name)
         .and_then(OsStr::to_str)
-        .and_then(|x| toolbox.tool(x).ok());
+        .and_then(|x| toolbox.tool_by_binary(x).ok());
 
     if let Some(tool) = arg0_tool {
+        cmd::outdated::update_notice(&toolbox);
         tool.run(args).await?;
     }
 
@@ -46,11 +55,21 @@ pub async fn run() -> Result<(), error::Error> {
         .subcommand(ToolboxCommand::command())
         .subcommand(CompletionCommand::command())
         .subcommand(ShellCommand::command())
+        .subcommand(SelfUpdateCommand::command())
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .help("never access the network, same as NK_OFFLINE=1"),
+        )
         .allow_hyphen_values(true);
 
     let command = toolbox.mount_toolbox(command).await?;
 
     let matches = command.clone().get_matches();
+    if matches.is_present("offline") {
+        toolbox.downloader().set_offline(true);
+    }
+    cmd::outdated::update_notice(&toolbox);
     match matches.subcommand() {
         Some(("copy", subcommand)) => {
             CopyCommand::from_arg_matches(subcommand)?
@@ -69,6 +88,11 @@ pub async fn run() -> Result<(), error::Error> {
             CompletionCommand::from_arg_matches(subcommand)?
                 .run(&command, &toolbox)?;
         }
+        Some(("self-update", subcommand)) => {
+            SelfUpdateCommand::from_arg_matches(subcommand)?
+                .run(&toolbox)
+                .await?
+        }
         Some(("toolbox", subcommand)) => {
             ToolboxCommand::from_arg_matches(subcommand)?
                 .run(&toolbox)
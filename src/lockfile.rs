@@ -0,0 +1,68 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::result::Result;
+
+pub static LOCKFILE_NAME: &str = ".nk.lock";
+
+/// exact versions, package urls and binary digests of the tools a project
+/// uses.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Lockfile {
+    #[serde(default)]
+    tools: BTreeMap<String, LockedTool>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct LockedTool {
+    version: String,
+    url: String,
+    sha256: String,
+}
+
+impl LockedTool {
+    pub fn new(version: String, url: String, sha256: String) -> Self {
+        Self {
+            version,
+            url,
+            sha256,
+        }
+    }
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read(path)?;
+        Ok(serde_yaml::from_slice(&content)
+            .map_err(|e| format!("{}: {}", path.display(), e))?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn tool(&self, name: &str) -> Option<&LockedTool> {
+        self.tools.get(name)
+    }
+
+    pub fn tools(&self) -> impl Iterator<Item = (&str, &LockedTool)> {
+        self.tools.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn insert(&mut self, name: &str, tool: LockedTool) {
+        self.tools.insert(name.to_string(), tool);
+    }
+}
@@ -66,6 +66,6 @@ impl<'a> PodExec<'a> {
         args.append(&mut vec![name, "--"]);
         let args = args.into_iter().map(OsString::from).chain(prog_args);
 
-        kubectl.command(args).await
+        kubectl.plain_command(args).await
     }
 }
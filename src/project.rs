@@ -0,0 +1,88 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    lockfile::LOCKFILE_NAME, result::Result, toolbox::tool::VersionRef,
+};
+
+pub static PROJECT_FILE_NAME: &str = ".nk.yaml";
+
+/// per project settings, read from a `.nk.yaml` in the current directory or
+/// any of its parents.
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Project {
+    #[serde(skip)]
+    path: PathBuf,
+    /// maps tool names to pinned versions
+    #[serde(default)]
+    tools: HashMap<String, String>,
+    /// values that the `env` and `args` templates of tools can refer to
+    #[serde(default)]
+    variables: HashMap<String, String>,
+}
+
+impl Project {
+    pub fn discover() -> Result<Option<Self>> {
+        let current_dir = env::current_dir()?;
+        for dir in current_dir.ancestors() {
+            let path = dir.join(PROJECT_FILE_NAME);
+            if path.is_file() {
+                return Ok(Some(Self::load(&path)?));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read(path)?;
+        let mut project: Self = serde_yaml::from_slice(&content)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        for (tool, version) in &project.tools {
+            let version_ref = version.parse::<VersionRef>().map_err(|e| {
+                format!("{}: tools.{}: {}", path.display(), tool, e)
+            })?;
+            if matches!(version_ref, VersionRef::Cluster) && tool != "kubectl" {
+                return Err(format!(
+                    "{}: tools.{}: `cluster` can only be used for kubectl",
+                    path.display(),
+                    tool
+                )
+                .into());
+            }
+        }
+        project.path = path.to_path_buf();
+        Ok(project)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.path.with_file_name(LOCKFILE_NAME)
+    }
+
+    pub fn pinned_version(&self, tool: &str) -> Option<&str> {
+        self.tools.get(tool).map(String::as_str)
+    }
+
+    /// the pinned version of `tool`, parsed
+    pub fn pinned_version_ref(&self, tool: &str) -> Option<VersionRef> {
+        self.pinned_version(tool)
+            .map(|v| v.parse().expect("pins are checked by Project::load"))
+    }
+
+    pub fn pinned_tools(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tools.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::{fs::File, io::AsyncReadExt};
+
+use super::{platform::Platform, tool::ToolDefinition};
+use crate::download::Downloader;
+use crate::result::Result;
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumDefinition {
+    /// url of a file that only contains the digest of the package
+    Sidecar(String),
+    /// url of a `checksums.txt` style file listing digests of many files
+    Manifest(ManifestChecksum),
+    /// the digest itself
+    Sha256(String),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ManifestChecksum {
+    url: String,
+    /// file name to look up in the manifest. Defaults to the file name of
+    /// the package url.
+    #[serde(default)]
+    file: Option<String>,
+}
+
+impl ChecksumDefinition {
+    /// url templates the digest is downloaded from
+    pub fn url_templates(&self) -> Vec<&str> {
+        match self {
+            ChecksumDefinition::Sidecar(url) => vec![url],
+            ChecksumDefinition::Manifest(manifest) => vec![&manifest.url],
+            _ => vec![],
+        }
+    }
+
+    /// other templates rendered when looking up the digest
+    pub fn templates(&self) -> Vec<&str> {
+        match self {
+            ChecksumDefinition::Manifest(manifest) => {
+                manifest.file.iter().map(String::as_str).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// downloads or looks up the digest. `package_urls` are the upstream
+    /// package url followed by its mirrors, see `mirrored_urls`.
+    pub async fn digest(
+        &self,
+        definition: &ToolDefinition,
+        version: &str,
+        platform: &Platform,
+        downloader: &Downloader,
+        package_urls: &[String],
+    ) -> Result<String> {
+        let msg = format!("{}-{} checksum", definition.name(), version);
+        let digest = match self {
+            ChecksumDefinition::Sha256(digest) => digest.to_string(),
+            ChecksumDefinition::Sidecar(url) => {
+                let url = definition.replace(url, version, platform)?;
+                let urls = mirrored_urls(&url, package_urls);
+                let response = fetch_any(downloader, &urls, &msg).await?;
+                parse_sidecar(&response)?
+            }
+            ChecksumDefinition::Manifest(manifest) => {
+                let url =
+                    definition.replace(&manifest.url, version, platform)?;
+                let file = match &manifest.file {
+                    Some(file) => {
+                        definition.replace(file, version, platform)?
+                    }
+                    None => {
+                        file_name(&definition.package_url(version, platform)?)
+                    }
+                };
+                let urls = mirrored_urls(&url, package_urls);
+                let response = fetch_any(downloader, &urls, &msg).await?;
+                parse_manifest(&response, &file)?
+            }
+        };
+        validate(&digest)
+    }
+}
+
+/// `url` followed by its counterparts next to the mirrored packages, if it
+/// is next to the upstream package, like the sidecar files of most tools.
+/// Other checksum urls have to be covered by `rewrites`.
+pub fn mirrored_urls(url: &str, package_urls: &[String]) -> Vec<String> {
+    let mut urls = vec![url.to_string()];
+    let rest = package_urls
+        .first()
+        .and_then(|package_url| package_url.rsplit_once('/'))
+        .and_then(|(dir, _)| url.strip_prefix(dir));
+    if let Some(rest) = rest {
+        for mirror in package_urls.iter().skip(1) {
+            if let Some((dir, _)) = mirror.rsplit_once('/') {
+                urls.push(format!("{}{}", dir, rest));
+            }
+        }
+    }
+    urls
+}
+
+/// downloads the first of `urls` that can be fetched
+async fn fetch_any(
+    downloader: &Downloader,
+    urls: &[String],
+    msg: &str,
+) -> Result<String> {
+    let mut error = None;
+    for url in urls {
+        match downloader.string(url, msg).await {
+            Ok(response) => return Ok(response),
+            Err(e) => error = Some(e),
+        }
+    }
+    Err(error.unwrap_or_else(|| "No checksum url".into()))
+}
+
+fn file_name(url: &str) -> String {
+    url.rsplit('/').next().unwrap_or(url).to_string()
+}
+
+fn validate(digest: &str) -> Result<String> {
+    let digest = digest.to_ascii_lowercase();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Malformed sha256 digest: {}", digest).into());
+    }
+    Ok(digest)
+}
+
+/// parses a sidecar file. These either contain only the digest or the
+/// digest followed by the file name.
+pub fn parse_sidecar(content: &str) -> Result<String> {
+    content
+        .split_whitespace()
+        .next()
+        .map(String::from)
+        .ok_or_else(|| "Empty checksum file".into())
+}
+
+/// looks up `file` in a manifest in the format `sha256sum` generates.
+pub fn parse_manifest(content: &str, file: &str) -> Result<String> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| {
+            let name = name.trim().trim_start_matches('*');
+            name == file || file_name(name) == file
+        })
+        .map(|(digest, _)| digest.to_string())
+        .ok_or_else(|| format!("No checksum found for {}", file).into())
+}
+
+/// sha256 digest of the file at `path`
+pub async fn file_digest(path: &Path) -> Result<String> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex_digest(hasher))
+}
+
+pub fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
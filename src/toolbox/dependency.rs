@@ -0,0 +1,71 @@
+use super::tool::{split_tool_spec, ToolDefinition};
+use crate::result::Result;
+
+/// resolves the transitive dependencies of the tool `name`. The result
+/// contains the dependency specs in topological order, so every dependency
+/// comes before the tools depending on it. The tool itself is not included.
+/// A dependency required both with and without a version keeps the version;
+/// different versions of the same dependency are an error.
+pub fn resolve<'a>(
+    tools: &'a [ToolDefinition],
+    name: &str,
+) -> Result<Vec<&'a str>> {
+    let definition = tools
+        .iter()
+        .find(|t| t.name() == name)
+        .ok_or_else(|| format!("Tool not found: {}", name))?;
+    let mut path = vec![definition.name()];
+    let mut closure = vec![];
+    for dep in definition.dependencies() {
+        visit(tools, dep, &mut path, &mut closure)?;
+    }
+    Ok(closure)
+}
+
+fn visit<'a>(
+    tools: &'a [ToolDefinition],
+    spec: &'a str,
+    path: &mut Vec<&'a str>,
+    closure: &mut Vec<&'a str>,
+) -> Result<()> {
+    let (name, _) = split_tool_spec(spec);
+    if let Some(start) = path.iter().position(|n| *n == name) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(name);
+        return Err(format!("Dependency cycle: {}", cycle.join(" -> ")).into());
+    }
+    if let Some(seen) =
+        closure.iter_mut().find(|s| split_tool_spec(s).0 == name)
+    {
+        if !spec.contains('@') || *seen == spec {
+            return Ok(());
+        }
+        if seen.contains('@') {
+            return Err(format!(
+                "Conflicting constraints {} and {} required by {}",
+                seen,
+                spec,
+                path.last().unwrap_or(&"")
+            )
+            .into());
+        }
+        *seen = spec;
+        return Ok(());
+    }
+
+    let definition =
+        tools.iter().find(|t| t.name() == name).ok_or_else(|| {
+            format!(
+                "Unknown dependency {} of {}",
+                name,
+                path.last().unwrap_or(&"")
+            )
+        })?;
+    path.push(definition.name());
+    for dep in definition.dependencies() {
+        visit(tools, dep, path, closure)?;
+    }
+    path.pop();
+    closure.push(spec);
+    Ok(())
+}
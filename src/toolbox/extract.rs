@@ -0,0 +1,247 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use tokio::{sync::mpsc, task};
+use xz2::read::XzDecoder;
+use zip::ZipArchive;
+
+use crate::download::DownloadSink;
+use crate::error::{Error, TError};
+
+/// downloaded chunks buffered for a slower extraction
+static CHANNEL_CAPACITY: usize = 16;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum ArchiveFormat {
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.xz")]
+    TarXz,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    #[serde(rename = "zip")]
+    Zip,
+    /// a single gzip compressed file
+    #[serde(rename = "gzip")]
+    Gzip,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ExtractDefinition {
+    format: ArchiveFormat,
+    /// path of the binary inside the archive. Ignored for `gzip`.
+    #[serde(default)]
+    path: String,
+}
+
+impl ExtractDefinition {
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl ArchiveFormat {
+    /// whether the format can be extracted while it is downloaded
+    pub fn is_streamable(&self) -> bool {
+        !matches!(self, ArchiveFormat::Zip)
+    }
+}
+
+/// extracts each `(member, dest)` pair of `members` from `archive`. This
+/// does blocking io.
+pub fn extract(
+    format: ArchiveFormat,
+    archive: &Path,
+    members: &[(String, PathBuf)],
+) -> Result<(), TError> {
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip =
+                ZipArchive::new(BufReader::new(File::open(archive)?))?;
+            for (member, dest) in members {
+                let mut entry = zip
+                    .by_name(member)
+                    .map_err(|_| format!("{} not found in archive", member))?;
+                io::copy(&mut entry, &mut File::create(dest)?)?;
+            }
+            Ok(())
+        }
+        format => extract_from(format, File::open(archive)?, members),
+    }
+}
+
+/// like `extract`, but reads the archive from `reader`. Zip archives can't
+/// be read this way. This does blocking io.
+pub fn extract_from<R: Read>(
+    format: ArchiveFormat,
+    reader: R,
+    members: &[(String, PathBuf)],
+) -> Result<(), TError> {
+    let reader = BufReader::new(reader);
+    match format {
+        ArchiveFormat::TarGz => extract_tar(GzDecoder::new(reader), members),
+        ArchiveFormat::TarXz => extract_tar(XzDecoder::new(reader), members),
+        ArchiveFormat::TarZst => {
+            extract_tar(zstd::Decoder::with_buffer(reader)?, members)
+        }
+        ArchiveFormat::Zip => {
+            Err("zip archives can't be extracted from a stream".into())
+        }
+        ArchiveFormat::Gzip => match members {
+            [(_, dest)] => {
+                let mut out = File::create(dest)?;
+                io::copy(&mut GzDecoder::new(reader), &mut out)?;
+                Ok(())
+            }
+            _ => Err("gzip packages contain exactly one file".into()),
+        },
+    }
+}
+
+/// extracts an archive on a blocking task while it is downloaded.
+/// Formats that can't be streamed are extracted from the complete download
+/// by `finish`.
+pub struct StreamingExtractor {
+    format: ArchiveFormat,
+    members: Vec<(String, PathBuf)>,
+    sender: Option<mpsc::Sender<Vec<u8>>>,
+    task: Option<task::JoinHandle<Result<(), TError>>>,
+}
+
+impl StreamingExtractor {
+    pub fn new(format: ArchiveFormat, members: Vec<(String, PathBuf)>) -> Self {
+        Self {
+            format,
+            members,
+            sender: None,
+            task: None,
+        }
+    }
+
+    /// waits for the extraction of the download completed at `path`
+    pub async fn finish(mut self, path: &Path) -> Result<(), Error> {
+        self.sender = None;
+        let task = match self.task.take() {
+            Some(task) => task,
+            None => {
+                let (format, members) = (self.format, self.members.clone());
+                let path = path.to_path_buf();
+                task::spawn_blocking(move || extract(format, &path, &members))
+            }
+        };
+        task.await?.map_err(|e| e as Error)
+    }
+
+    /// stops the extraction of a failed download
+    pub async fn abort(mut self) {
+        self.sender = None;
+        if let Some(task) = self.task.take() {
+            task.await.ok();
+        }
+    }
+}
+
+impl DownloadSink for StreamingExtractor {
+    fn restart<'a>(
+        &'a mut self,
+        path: &'a Path,
+        offset: u64,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            if !self.format.is_streamable() {
+                return Ok(());
+            }
+            // the previous extraction reads to the end of its truncated
+            // input and fails. Its result is discarded, it only has to be
+            // done writing before a new extraction starts.
+            self.sender = None;
+            if let Some(task) = self.task.take() {
+                task.await.ok();
+            }
+            let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+            let file = File::open(path)?;
+            let reader = file.take(offset).chain(ChannelReader {
+                receiver,
+                chunk: vec![],
+                pos: 0,
+            });
+            let (format, members) = (self.format, self.members.clone());
+            self.task = Some(task::spawn_blocking(move || {
+                extract_from(format, reader, &members)
+            }));
+            self.sender = Some(sender);
+            Ok(())
+        })
+    }
+
+    fn write<'a>(&'a mut self, chunk: &'a [u8]) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if let Some(sender) = &self.sender {
+                // fails once the extraction is done, the rest isn't needed
+                sender.send(chunk.to_vec()).await.ok();
+            }
+        })
+    }
+}
+
+/// reads the chunks sent to `receiver` until the sender is dropped
+struct ChannelReader {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+fn extract_tar<R: Read>(
+    reader: R,
+    members: &[(String, PathBuf)],
+) -> Result<(), TError> {
+    let mut missing: Vec<_> = members.iter().collect();
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let path = path.strip_prefix(".").unwrap_or(&path);
+        let (found, rest): (Vec<_>, Vec<_>) =
+            missing.into_iter().partition(|(m, _)| path == Path::new(m));
+        missing = rest;
+        if let Some(((_, first), others)) = found.split_first() {
+            io::copy(&mut entry, &mut File::create(first)?)?;
+            for (_, dest) in others {
+                std::fs::copy(first, dest)?;
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+    }
+    let missing: Vec<_> = missing.iter().map(|(m, _)| m.as_str()).collect();
+    Err(format!("{} not found in archive", missing.join(", ")).into())
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use super::{
+    dependency, platform::Platform, repository::Repository,
+    tool::ToolDefinition,
+};
+use crate::result::Result;
+
+/// version templates are rendered with. Only its format matters.
+static LINT_VERSION: &str = "v1.2.3";
+
+/// checks a repository for mistakes that deserializing it doesn't catch.
+/// Templates are rendered for every platform nk is released for. Returns a
+/// description of each problem found.
+pub fn lint(repository: &Repository) -> Vec<String> {
+    let mut problems = Problems::default();
+    let tools = repository.tools();
+
+    let defaults = [
+        ("with_subcommand", repository.default_with_subcommand()),
+        ("no_subcommand", repository.default_no_subcommand()),
+    ];
+    for (key, name) in defaults {
+        if !tools.iter().any(|t| t.name() == name) {
+            problems.push(format!("default.{}: unknown tool {}", key, name));
+        }
+    }
+
+    let mut owners: HashMap<&str, &str> = HashMap::new();
+    for tool in tools {
+        for name in names(tool) {
+            match owners.insert(name, tool.name()) {
+                Some(owner) if owner == tool.name() => {
+                    problems.push(format!("{}: {} is used twice", owner, name))
+                }
+                Some(owner) => problems.push(format!(
+                    "{} is used by both {} and {}",
+                    name,
+                    owner,
+                    tool.name()
+                )),
+                None => {}
+            }
+        }
+    }
+
+    for tool in tools {
+        if let Err(e) = dependency::resolve(tools, tool.name()) {
+            problems.push(e.to_string());
+        }
+        if !tool.binaries().is_empty() && tool.extract().is_none() {
+            problems.push(format!(
+                "{}: binaries can only be installed from an archive",
+                tool.name()
+            ));
+        }
+        let extract_path = tool.extract().map(|e| e.path());
+        for binary in tool.binaries() {
+            if Some(binary.path()) == extract_path {
+                problems.push(format!(
+                    "{}: binary {} is extract.path, use an alias instead",
+                    tool.name(),
+                    binary.name()
+                ));
+            }
+        }
+        lint_templates(&mut problems, tool);
+    }
+    problems.0
+}
+
+#[derive(Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+    fn push(&mut self, problem: String) {
+        if !self.0.contains(&problem) {
+            self.0.push(problem);
+        }
+    }
+}
+
+/// names a tool can be called by
+fn names(tool: &ToolDefinition) -> Vec<&str> {
+    let mut names = vec![tool.name()];
+    names.extend(tool.aliases().iter().map(String::as_str));
+    for binary in tool.binaries() {
+        names.push(binary.name());
+        names.extend(binary.alias());
+    }
+    names
+}
+
+/// renders all templates of `tool` for every platform
+fn lint_templates(problems: &mut Problems, tool: &ToolDefinition) {
+    for platform in Platform::released() {
+        for (field, result) in render(tool, &platform) {
+            if let Err(e) = result {
+                problems.push(format!("{}: {}: {}", tool.name(), field, e));
+            }
+        }
+    }
+}
+
+/// renders every template of `tool` on `platform`, labeled with the field
+/// it comes from
+fn render(
+    tool: &ToolDefinition,
+    platform: &Platform,
+) -> Vec<(&'static str, Result<String>)> {
+    let mut rendered = vec![
+        ("package_url", tool.package_url(LINT_VERSION, platform)),
+        (
+            "extract_command",
+            tool.extract_command(LINT_VERSION, platform)
+                .map(|c| c.join(" ")),
+        ),
+    ];
+    if let Some(extract) = tool.extract() {
+        rendered.push((
+            "extract.path",
+            tool.replace(extract.path(), LINT_VERSION, platform),
+        ));
+    }
+    for binary in tool.binaries_for(platform) {
+        rendered.push((
+            "binaries.path",
+            tool.replace(binary.path(), LINT_VERSION, platform),
+        ));
+    }
+    if let Some(checksum) = tool.checksum() {
+        let templates = checksum.url_templates();
+        for template in templates.into_iter().chain(checksum.templates()) {
+            rendered.push((
+                "checksum",
+                tool.replace(template, LINT_VERSION, platform),
+            ));
+        }
+    }
+    rendered
+}
+
+/// package and checksum urls of `version` of `tool` for every platform nk
+/// is released for. Templates that fail to render are left out.
+pub fn urls(tool: &ToolDefinition, version: &str) -> Vec<String> {
+    let mut urls = vec![];
+    for platform in Platform::released() {
+        let templates = tool
+            .checksum()
+            .map(|c| c.url_templates())
+            .unwrap_or_default();
+        let rendered = templates
+            .into_iter()
+            .map(|t| tool.replace(t, version, &platform))
+            .chain(std::iter::once(tool.package_url(version, &platform)));
+        for url in rendered.flatten() {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
@@ -1,34 +1,108 @@
-mod repository;
+pub mod checksum;
+pub mod dependency;
+pub mod extract;
+pub mod lint;
+pub mod platform;
+pub mod repository;
 pub mod tool;
-mod upstream;
+pub mod upstream;
 
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 
-use crate::{dirs::Dirs, download::Downloader, result::Result};
+use crate::{
+    config::Config, dirs::Dirs, download::Downloader, lockfile::Lockfile,
+    project::Project, result::Result,
+};
 
 use self::{
+    platform::Platform,
     repository::Repository,
-    tool::{Tool, ToolDefinition, VersionRef},
+    tool::{parse_tool_spec, Tool, ToolDefinition, VersionRef},
 };
 
-static REPOSITORY: &[u8] =
+pub static REPOSITORY: &[u8] =
     include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/repository.yaml"));
 
 //static REPOSITORY_URL: &'static str = "https://neatkube.withlazers.dev/repository.yaml";
 
 pub struct Toolbox {
+    config: Config,
     repository: Repository,
+    /// the repository in use as yaml, including imported tools
+    repository_source: Vec<u8>,
     downloader: Downloader,
+    project: Option<Project>,
+    /// why the project was ignored, reported by the project commands
+    project_error: Option<String>,
+    lockfile: Option<Lockfile>,
+    installing: Mutex<HashMap<PathBuf, Arc<AsyncMutex<()>>>>,
+    platform: Platform,
 }
 
 impl Toolbox {
-    pub async fn create() -> Result<Self> {
+    /// a toolbox installing tools for `platform`, usually
+    /// `Platform::current()`
+    pub async fn create(platform: Platform) -> Result<Self> {
+        // a broken project file only breaks the project commands
+        let (project, lockfile, project_error) = match load_project() {
+            Ok((project, lockfile)) => (project, lockfile, None),
+            Err(e) => {
+                eprintln!("Warning: ignoring the project: {}", e);
+                (None, None, Some(e.to_string()))
+            }
+        };
+        Self::load(platform, project, project_error, lockfile)
+    }
+
+    /// a toolbox for `platform` with a freshly loaded repository. The
+    /// project and the offline mode of this one are kept.
+    pub async fn with_platform(&self, platform: Platform) -> Result<Self> {
+        let toolbox = Self::load(
+            platform,
+            self.project.clone(),
+            self.project_error.clone(),
+            self.lockfile.clone(),
+        )?;
+        toolbox.downloader.set_offline(self.downloader.is_offline());
+        Ok(toolbox)
+    }
+
+    fn load(
+        platform: Platform,
+        project: Option<Project>,
+        project_error: Option<String>,
+        lockfile: Option<Lockfile>,
+    ) -> Result<Self> {
+        let config = Config::load()?;
+        let repository_source = load_repository()?;
         Ok(Self {
-            repository: serde_yaml::from_slice(REPOSITORY)?,
-            downloader: Downloader::default(),
+            downloader: Downloader::new(&config)?,
+            config,
+            repository: Repository::from_slice(&repository_source)?,
+            repository_source,
+            project,
+            project_error,
+            lockfile,
+            installing: Mutex::default(),
+            platform,
         })
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// platform tools are installed for
+    pub fn platform(&self) -> &Platform {
+        &self.platform
+    }
+
     pub fn downloader(&self) -> &Downloader {
         &self.downloader
     }
@@ -37,12 +111,61 @@ impl Toolbox {
         &self.repository
     }
 
+    pub fn repository_source(&self) -> &[u8] {
+        &self.repository_source
+    }
+
+    /// directory repositories imported by `toolbox bundle import` are kept
+    /// in. Their tools are added to the ones of the built-in repository.
+    pub fn repositories_dir_path() -> Result<PathBuf> {
+        Ok(Dirs::data_dir()?.join("repositories"))
+    }
+
+    pub fn project(&self) -> Option<&Project> {
+        self.project.as_ref()
+    }
+
+    /// the project, or why there is none
+    pub fn required_project(&self) -> Result<&Project> {
+        match (&self.project, &self.project_error) {
+            (Some(project), _) => Ok(project),
+            (None, Some(e)) => Err(e.clone().into()),
+            (None, None) => Err("No project file found".into()),
+        }
+    }
+
+    pub fn lockfile(&self) -> Option<&Lockfile> {
+        self.lockfile.as_ref()
+    }
+
+    /// serializes concurrent installs into the same directory
+    pub async fn install_lock(&self, path: &Path) -> OwnedMutexGuard<()> {
+        let lock = self
+            .installing
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+
     pub fn bin_dir_path(&self) -> Result<PathBuf> {
         Ok(Dirs::data_dir()?.join("bin"))
     }
 
+    /// directory tools are installed into. Tools for other platforms than
+    /// the current one are kept apart, so they never shadow native ones.
     pub fn exec_dir_path(&self) -> Result<PathBuf> {
-        Ok(Dirs::data_dir()?.join("exec"))
+        let platform = self.platform();
+        let data_dir = Dirs::data_dir()?;
+        match platform.is_current() {
+            true => Ok(data_dir.join("exec")),
+            false => Ok(data_dir
+                .join("platforms")
+                .join(platform.to_string())
+                .join("exec")),
+        }
     }
 
     pub async fn installed_tools(&self) -> Result<Vec<&ToolDefinition>> {
@@ -76,8 +199,70 @@ impl Toolbox {
             .ok_or_else(|| format!("Tool not found: {name}").into())
     }
 
+    /// version refs to use for `name` if none are given explicitly. Tools
+    /// locked or pinned by the project use the locked or pinned version.
+    pub fn default_version_refs(&self, name: &str) -> Vec<VersionRef> {
+        match self.lockfile().and_then(|l| l.tool(name)) {
+            Some(locked) => {
+                vec![VersionRef::Specific(locked.version().to_string())]
+            }
+            None => self.pinned_version_refs(name),
+        }
+    }
+
+    /// like `default_version_refs`, but ignores the lockfile.
+    pub fn pinned_version_refs(&self, name: &str) -> Vec<VersionRef> {
+        match self.project().and_then(|p| p.pinned_version_ref(name)) {
+            Some(version_ref) => vec![version_ref],
+            None => vec![VersionRef::Local, VersionRef::Latest],
+        }
+    }
+
     pub fn tool<'a>(&'a self, name: &str) -> Result<Tool<'a>> {
-        self.tool_with_version(name, [VersionRef::Local, VersionRef::Latest])
+        self.tool_with_version(name, self.default_version_refs(name))
+    }
+
+    /// looks up a dependency given as `tool` or `tool@version`. Versions
+    /// locked or pinned by the project take precedence.
+    pub fn dependency<'a>(&'a self, spec: &str) -> Result<Tool<'a>> {
+        let (name, version_ref) = parse_tool_spec(spec)?;
+        let pinned = self.lockfile().and_then(|l| l.tool(name)).is_some()
+            || self
+                .project()
+                .and_then(|p| p.pinned_version(name))
+                .is_some();
+        match version_ref {
+            Some(version_ref) if !pinned => {
+                self.tool_with_version(name, vec![version_ref])
+            }
+            _ => self.tool(name),
+        }
+    }
+
+    /// all transitive dependencies of the tool `name`, each one before the
+    /// tools depending on it
+    pub fn dependencies<'a>(&'a self, name: &str) -> Result<Vec<Tool<'a>>> {
+        dependency::resolve(self.repository.tools(), name)?
+            .into_iter()
+            .map(|spec| self.dependency(spec))
+            .collect()
+    }
+
+    /// finds the tool providing the executable `name`. This is either the
+    /// tool itself, one of its aliases or one of its additional binaries.
+    pub fn tool_by_binary<'a>(&'a self, name: &str) -> Result<Tool<'a>> {
+        if let Ok(tool) = self.tool(name) {
+            return Ok(tool);
+        }
+        for definition in self.repository.tools() {
+            if definition.aliases().iter().any(|a| a == name) {
+                return self.tool(definition.name());
+            }
+            if let Some(binary) = definition.binary(name) {
+                return Ok(self.tool(definition.name())?.with_binary(binary));
+            }
+        }
+        Err(format!("Tool not found: {name}").into())
     }
 
     pub async fn mount_toolbox<'a>(
@@ -93,3 +278,46 @@ impl Toolbox {
         Ok(command)
     }
 }
+
+fn load_project() -> Result<(Option<Project>, Option<Lockfile>)> {
+    let project = Project::discover()?;
+    let lockfile = match &project {
+        Some(project) if project.lockfile_path().exists() => {
+            Some(Lockfile::load(&project.lockfile_path())?)
+        }
+        _ => None,
+    };
+    Ok((project, lockfile))
+}
+
+/// merges the repository at `path` into `source` and checks the result
+fn merge_repository(source: &[u8], path: &Path) -> Result<Vec<u8>> {
+    let merged = repository::merge(source, &std::fs::read(path)?)?;
+    Repository::from_slice(&merged)?;
+    Ok(merged)
+}
+
+/// the built-in repository merged with the imported ones. Built-in
+/// definitions take precedence.
+fn load_repository() -> Result<Vec<u8>> {
+    let mut source = REPOSITORY.to_vec();
+    let dir = Toolbox::repositories_dir_path()?;
+    if !dir.is_dir() {
+        return Ok(source);
+    }
+    let mut paths = std::fs::read_dir(&dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    paths.sort();
+    for path in paths {
+        if path.extension() != Some("yaml".as_ref()) {
+            continue;
+        }
+        // a broken file, or one from a newer nk, must not break nk
+        match merge_repository(&source, &path) {
+            Ok(merged) => source = merged,
+            Err(e) => eprintln!("Warning: ignoring {}: {}", path.display(), e),
+        }
+    }
+    Ok(source)
+}
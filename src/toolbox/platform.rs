@@ -0,0 +1,124 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::error::Error;
+use crate::result::Result;
+
+/// an operating system and architecture, named like Go does, e.g. `linux`
+/// and `amd64`. Tool definitions map these names to the ones upstream uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    os: String,
+    arch: String,
+}
+
+static KNOWN_OS: &[&str] = &["linux", "darwin", "windows", "freebsd"];
+static KNOWN_ARCH: &[&str] = &[
+    "amd64", "arm64", "386", "arm", "ppc64le", "s390x", "riscv64",
+];
+
+/// platforms nk is released for, see `.github/workflows/release.yaml`
+pub static RELEASED_PLATFORMS: &[(&str, &str)] = &[
+    ("linux", "amd64"),
+    ("linux", "arm64"),
+    ("linux", "386"),
+    ("linux", "arm"),
+    ("darwin", "amd64"),
+    ("darwin", "arm64"),
+];
+
+impl Platform {
+    pub fn new(os: &str, arch: &str) -> Self {
+        Self {
+            os: os.to_string(),
+            arch: arch.to_string(),
+        }
+    }
+
+    /// a platform from names given by a user. Common alternative names like
+    /// `macos` or `aarch64` are accepted, unknown ones are rejected.
+    pub fn parse(os: &str, arch: &str) -> Result<Self> {
+        let os = match os {
+            "macos" | "osx" => "darwin",
+            x => x,
+        };
+        let arch = match arch {
+            "x86_64" | "x64" => "amd64",
+            "aarch64" => "arm64",
+            "x86" | "i386" | "i686" => "386",
+            "armv7" | "armv7l" | "armhf" => "arm",
+            "powerpc64le" | "ppc64el" => "ppc64le",
+            x => x,
+        };
+        if !KNOWN_OS.contains(&os) {
+            return Err(format!(
+                "Unknown operating system: {}, expected one of {}",
+                os,
+                KNOWN_OS.join(", ")
+            )
+            .into());
+        }
+        if !KNOWN_ARCH.contains(&arch) {
+            return Err(format!(
+                "Unknown architecture: {}, expected one of {}",
+                arch,
+                KNOWN_ARCH.join(", ")
+            )
+            .into());
+        }
+        Ok(Self::new(os, arch))
+    }
+
+    /// the platform nk is running on
+    pub fn current() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "darwin",
+            x => x,
+        };
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "x86" => "386",
+            "aarch64" => "arm64",
+            "powerpc64" if cfg!(target_endian = "little") => "ppc64le",
+            "powerpc64" => "ppc64",
+            x => x,
+        };
+        Self::new(os, arch)
+    }
+
+    pub fn is_current(&self) -> bool {
+        *self == Self::current()
+    }
+
+    pub fn released() -> Vec<Self> {
+        RELEASED_PLATFORMS
+            .iter()
+            .map(|(os, arch)| Self::new(os, arch))
+            .collect()
+    }
+
+    pub fn os(&self) -> &str {
+        &self.os
+    }
+
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+}
+
+/// parses the `os-arch` form `Display` produces
+impl FromStr for Platform {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('-') {
+            Some((os, arch)) => Self::parse(os, arch),
+            None => Err(format!("Malformed platform: {}", s).into()),
+        }
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.os, self.arch)
+    }
+}
@@ -1,20 +1,26 @@
 use super::tool::ToolDefinition;
+use crate::result::Result;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct DefaultTools {
     with_subcommand: String,
     no_subcommand: String,
 }
 
+/// the tool definitions, read from a `repository.yaml`. Unknown keys are
+/// rejected, so typos don't go unnoticed.
 #[derive(Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct Repository {
     default: DefaultTools,
     tools: Vec<ToolDefinition>,
 }
 impl Repository {
+    pub fn from_slice(content: &[u8]) -> Result<Self> {
+        Ok(serde_yaml::from_slice(content)?)
+    }
     pub fn tools(&self) -> &[ToolDefinition] {
         &self.tools
     }
@@ -25,3 +31,25 @@ impl Repository {
         &self.default.no_subcommand
     }
 }
+
+/// adds the tools of the repository `extra` that `base` doesn't define to
+/// `base`. Returns the merged repository as yaml.
+pub fn merge(base: &[u8], extra: &[u8]) -> Result<Vec<u8>> {
+    let mut base: serde_yaml::Value = serde_yaml::from_slice(base)?;
+    let extra: serde_yaml::Value = serde_yaml::from_slice(extra)?;
+    let name = |tool: &serde_yaml::Value| tool.get("name").cloned();
+    let extra_tools = match extra.get("tools") {
+        Some(serde_yaml::Value::Sequence(tools)) => tools.clone(),
+        _ => return Err("repository has no tools".into()),
+    };
+    let tools = match base.get_mut("tools") {
+        Some(serde_yaml::Value::Sequence(tools)) => tools,
+        _ => return Err("repository has no tools".into()),
+    };
+    for tool in extra_tools {
+        if !tools.iter().any(|t| name(t) == name(&tool)) {
+            tools.push(tool);
+        }
+    }
+    Ok(serde_yaml::to_vec(&base)?)
+}
@@ -1,78 +1,174 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env,
     ffi::{CString, OsStr, OsString},
     iter,
     ops::{Deref, DerefMut},
     os::unix::prelude::{OsStrExt, PermissionsExt},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Stdio,
     str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use crate::cluster;
 use crate::download::Downloader;
 use crate::error::Error;
 use crate::result::Result;
 use clap::Arg;
+use futures::{future::join_all, join};
 use nix::unistd::execve;
 use os_str_bytes::OsStrBytes;
+use semver::{Version, VersionReq};
 use serde::Deserialize;
-use tokio::{
-    fs, fs::File, io::AsyncReadExt, io::AsyncWriteExt, process::Command,
-    sync::Mutex,
-};
-use tokio_stream::StreamExt;
+use tokio::{fs, process::Command, sync::Mutex};
 
 use super::{
-    upstream::{Upstream, UpstreamDefinition},
+    checksum::{self, ChecksumDefinition},
+    extract::{ExtractDefinition, StreamingExtractor},
+    platform::Platform,
+    upstream::{Release, Upstream, UpstreamDefinition},
     Toolbox,
 };
 use dewey::VersionCmp;
 
 static ARGS_NAME: &str = "args";
+pub static LAST_USED_FILE_NAME: &str = ".last_used";
+static ACTIVE_FILE_NAME: &str = ".active";
+/// records the package a version was installed from, like `sha256sum`
+static PACKAGE_FILE_NAME: &str = ".package";
+/// upper bound of pages read when listing upstream versions
+static MAX_VERSION_PAGES: usize = 10;
 
 #[derive(Debug, Clone)]
 pub enum VersionRef {
     Latest,
     Local,
     Specific(String),
+    Range(VersionReq),
+    /// a version within the supported version skew of the api server of
+    /// the current kubernetes context
+    Cluster,
 }
 
-impl VersionRef {}
+impl VersionRef {
+    /// parses version ranges like `^3.10`, `~1.24` or `v1.24.*`. Returns
+    /// `None` for anything that doesn't look like a range and an error for
+    /// ranges that are malformed, like `^3.x.y`.
+    pub fn parse_range(s: &str) -> Result<Option<VersionReq>> {
+        let is_range =
+            s.contains('*') || s.starts_with(['^', '~', '=', '>', '<']);
+        if !is_range {
+            return Ok(None);
+        }
 
-impl FromStr for VersionRef {
-    type Err = core::convert::Infallible;
+        // strip the `v` prefix of versions, as in `^v3.10`
+        let mut normalized = String::with_capacity(s.len());
+        let mut prev = ' ';
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let next_is_digit =
+                matches!(chars.peek(), Some(n) if n.is_ascii_digit());
+            if !(c == 'v' && next_is_digit && !prev.is_ascii_alphanumeric()) {
+                normalized.push(c);
+            }
+            prev = c;
+        }
+        match VersionReq::parse(&normalized) {
+            Ok(range) => Ok(Some(range)),
+            Err(e) => {
+                Err(format!("Malformed version range {}: {}", s, e).into())
+            }
+        }
+    }
+}
+
+/// parses a version like `v1.24.3` as semantic version
+pub fn parse_semver(version: &str) -> Option<Version> {
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
 
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        Ok(s.into())
+/// splits a `tool@version` spec into the tool name and its version
+pub fn split_tool_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
     }
 }
 
-impl From<String> for VersionRef {
-    fn from(s: String) -> Self {
-        match s.as_str() {
+/// like `split_tool_spec`, but parses the version
+pub fn parse_tool_spec(spec: &str) -> Result<(&str, Option<VersionRef>)> {
+    let (name, version) = split_tool_spec(spec);
+    match version {
+        Some(version) => Ok((name, Some(version.parse()?))),
+        None => Ok((name, None)),
+    }
+}
+
+impl FromStr for VersionRef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
             "latest" => VersionRef::Latest,
             "local" => VersionRef::Local,
-            _ => VersionRef::Specific(s),
-        }
+            "cluster" => VersionRef::Cluster,
+            _ => match VersionRef::parse_range(s)? {
+                Some(range) => VersionRef::Range(range),
+                None => VersionRef::Specific(s.to_string()),
+            },
+        })
     }
 }
 
-impl From<&str> for VersionRef {
-    fn from(s: &str) -> Self {
-        Self::from(s.to_string())
+/// an additional executable shipped in the package of a tool
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct BinaryDefinition {
+    /// file name of the binary in the exec dir
+    name: String,
+    /// path of the binary inside the archive
+    path: String,
+    /// additional name the binary can be called by
+    #[serde(default)]
+    alias: Option<String>,
+    /// platforms like `linux-amd64` the package contains the binary for.
+    /// Empty for all.
+    #[serde(default)]
+    platforms: Vec<String>,
+}
+
+impl BinaryDefinition {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn alias(&self) -> Option<&str> {
+        self.alias.as_deref()
+    }
+    pub fn is_shipped_for(&self, platform: &Platform) -> bool {
+        self.platforms.is_empty()
+            || self.platforms.iter().any(|p| *p == platform.to_string())
     }
 }
 
 #[derive(Deserialize, Clone)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct ToolDefinition {
     name: String,
     description: String,
     upstream: UpstreamDefinition,
     #[serde(default)]
+    extract: Option<ExtractDefinition>,
+    #[serde(default)]
     extract_command: String,
     #[serde(default)]
+    binaries: Vec<BinaryDefinition>,
+    #[serde(default)]
+    checksum: Option<ChecksumDefinition>,
+    #[serde(default)]
     dependencies: Vec<String>,
     #[serde(default)]
     aliases: Vec<String>,
@@ -82,6 +178,22 @@ pub struct ToolDefinition {
     os_map: HashMap<String, String>,
     #[serde(default)]
     arch_map: HashMap<String, String>,
+    /// arguments that make the tool print its version. Empty if there are
+    /// none.
+    #[serde(default = "default_version_args")]
+    version_args: Vec<String>,
+    /// environment variables set when running the tool, unless they are
+    /// already set. Values are templates.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// templates of arguments passed before the ones given on the command
+    /// line
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+fn default_version_args() -> Vec<String> {
+    vec!["--version".to_string()]
 }
 
 impl ToolDefinition {
@@ -91,47 +203,84 @@ impl ToolDefinition {
     pub fn description(&self) -> &str {
         &self.description
     }
-    fn replace(&self, input: &str, version: &str) -> Result<String> {
-        let os_arch = self.os_arch();
+    pub fn dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+    pub fn binaries(&self) -> &[BinaryDefinition] {
+        &self.binaries
+    }
+    pub fn version_args(&self) -> &[String] {
+        &self.version_args
+    }
+    pub fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+    /// the additional binaries the package contains on `platform`
+    pub fn binaries_for(&self, platform: &Platform) -> Vec<&BinaryDefinition> {
+        self.binaries
+            .iter()
+            .filter(|b| b.is_shipped_for(platform))
+            .collect()
+    }
+    /// finds an additional binary by its name or alias
+    pub fn binary(&self, name: &str) -> Option<&BinaryDefinition> {
+        self.binaries
+            .iter()
+            .find(|b| b.name == name || b.alias() == Some(name))
+    }
+    /// renders the template `input` for `version` on `platform`
+    pub fn replace(
+        &self,
+        input: &str,
+        version: &str,
+        platform: &Platform,
+    ) -> Result<String> {
+        self.replace_with(input, version, platform, &HashMap::new())
+    }
+
+    /// like `replace`, but `variables` can be used in the template as well
+    pub fn replace_with(
+        &self,
+        input: &str,
+        version: &str,
+        platform: &Platform,
+        variables: &HashMap<String, String>,
+    ) -> Result<String> {
+        let os_arch = self.os_arch(platform);
         Ok(minitmpl::minitmpl_fn(input, |x| match x {
             "name" => Some(self.name.as_str()),
             "version" => Some(version),
-            "os" => Some(self.os()),
+            "os" => Some(self.os(platform)),
             "os_arch" => Some(&os_arch),
-            "arch" => Some(self.arch()),
+            "arch" => Some(self.arch(platform)),
             "stripped_version" => version.strip_prefix('v').or(Some(version)),
-            _ => None,
+            _ => variables.get(x).map(String::as_str),
         })?)
     }
 
-    pub fn os_arch(&self) -> String {
-        let default = format!("{}-{}", self.os(), self.arch());
+    pub fn os_arch(&self, platform: &Platform) -> String {
+        let default = format!("{}-{}", self.os(platform), self.arch(platform));
         self.os_arch_map.get(&default).cloned().unwrap_or(default)
     }
-    pub fn os(&self) -> &str {
-        let default = match std::env::consts::OS {
-            "macos" => "darwin",
-            x => x,
-        };
 
+    pub fn os<'b>(&'b self, platform: &'b Platform) -> &'b str {
         self.os_map
-            .get(default)
+            .get(platform.os())
             .map(String::as_str)
-            .unwrap_or(default)
+            .unwrap_or_else(|| platform.os())
     }
 
-    pub fn arch(&self) -> &str {
-        let default = match std::env::consts::ARCH {
-            "x86_64" => "amd64",
-            "x86" => "386",
-            "aarch64" => "arm64",
-            x => x,
-        };
-
+    pub fn arch<'b>(&'b self, platform: &'b Platform) -> &'b str {
         self.arch_map
-            .get(default)
+            .get(platform.arch())
             .map(String::as_str)
-            .unwrap_or(default)
+            .unwrap_or_else(|| platform.arch())
     }
 
     fn upstream<'a>(&'a self) -> Box<dyn Upstream + 'a> {
@@ -141,16 +290,32 @@ impl ToolDefinition {
         }
     }
 
-    pub fn extract_command(&self, version: &str) -> Result<Vec<String>> {
+    pub fn extract(&self) -> Option<&ExtractDefinition> {
+        self.extract.as_ref()
+    }
+
+    pub fn extract_command(
+        &self,
+        version: &str,
+        platform: &Platform,
+    ) -> Result<Vec<String>> {
         self.extract_command
             .split(' ')
             .filter(|x| !x.is_empty())
-            .map(|x| self.replace(x, version))
+            .map(|x| self.replace(x, version, platform))
             .collect()
     }
 
-    pub fn package_url(&self, version: &str) -> Result<String> {
-        self.replace(&self.upstream().package_url(), version)
+    pub fn package_url(
+        &self,
+        version: &str,
+        platform: &Platform,
+    ) -> Result<String> {
+        self.replace(&self.upstream().package_url(), version, platform)
+    }
+
+    pub fn checksum(&self) -> Option<&ChecksumDefinition> {
+        self.checksum.as_ref()
     }
 }
 
@@ -158,6 +323,7 @@ pub struct Tool<'a> {
     pub toolbox: &'a Toolbox,
     pub definition: &'a ToolDefinition,
     pub version: Mutex<Vec<VersionRef>>,
+    pub binary: Option<&'a BinaryDefinition>,
 }
 
 impl<'a> Tool<'a> {
@@ -166,6 +332,7 @@ impl<'a> Tool<'a> {
             toolbox,
             definition,
             version: Mutex::new(vec![VersionRef::Local, VersionRef::Latest]),
+            binary: None,
         }
     }
     pub fn new_with_version<I: IntoIterator<Item = VersionRef>>(
@@ -177,9 +344,27 @@ impl<'a> Tool<'a> {
             definition,
             toolbox,
             version: Mutex::new(version_refs.into_iter().collect()),
+            binary: None,
         }
     }
 
+    pub fn with_version<I: IntoIterator<Item = VersionRef>>(
+        self,
+        version_refs: I,
+    ) -> Self {
+        Self {
+            version: Mutex::new(version_refs.into_iter().collect()),
+            ..self
+        }
+    }
+
+    /// runs one of the additional binaries of the tool instead of the main
+    /// one
+    pub fn with_binary(mut self, binary: &'a BinaryDefinition) -> Self {
+        self.binary = Some(binary);
+        self
+    }
+
     pub async fn resolve_version(&self) -> Result<String> {
         let mut guard = self.version.lock().await;
         let version_refs = guard.deref();
@@ -189,6 +374,10 @@ impl<'a> Tool<'a> {
             let version = match version_ref {
                 VersionRef::Latest => Some(self.find_latest_version().await?),
                 VersionRef::Local => self.find_local_version().await?,
+                VersionRef::Range(range) => {
+                    Some(self.find_version_in_range(range).await?)
+                }
+                VersionRef::Cluster => Some(self.find_cluster_version().await?),
                 VersionRef::Specific(version) => {
                     return Ok(version.to_string());
                 }
@@ -227,26 +416,98 @@ impl<'a> Tool<'a> {
         Ok(path)
     }
 
+    /// name of the binary that gets executed
+    pub fn binary_name(&self) -> &str {
+        match self.binary {
+            Some(binary) => binary.name(),
+            None => self.name(),
+        }
+    }
+
     async fn exec_path(&self) -> Result<PathBuf> {
-        let name = &self.name();
+        let name = self.binary_name();
         Ok(self.exec_dir_path().await?.join(name))
     }
 
+    /// the active version if one was selected, otherwise the highest
+    /// installed one
     pub async fn find_local_version(&self) -> Result<Option<String>> {
         let all_versions = self.find_local_versions().await?;
+        if let Some(active) = self.find_active_version().await? {
+            if all_versions.contains(&active) {
+                return Ok(Some(active));
+            }
+        }
 
         Ok(all_versions.into_iter().last())
     }
 
+    fn active_version_path(&self) -> Result<PathBuf> {
+        Ok(self
+            .toolbox
+            .exec_dir_path()?
+            .join(self.name())
+            .join(ACTIVE_FILE_NAME))
+    }
+
+    /// the version selected with `toolbox use`
+    pub async fn find_active_version(&self) -> Result<Option<String>> {
+        match fs::read_to_string(self.active_version_path()?).await {
+            Ok(version) if !version.trim().is_empty() => {
+                Ok(Some(version.trim().to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// persists the version `VersionRef::Local` resolves to. `None` goes
+    /// back to the highest installed version.
+    pub async fn set_active_version(
+        &self,
+        version: Option<&str>,
+    ) -> Result<()> {
+        let path = self.active_version_path()?;
+        match version {
+            Some(version) => {
+                fs::create_dir_all(path.parent().unwrap()).await?;
+                fs::write(&path, version).await?;
+            }
+            None if path.exists() => fs::remove_file(&path).await?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// finds the highest local version matching `range`, falling back to
+    /// the highest matching upstream version.
+    async fn find_version_in_range(
+        &self,
+        range: &VersionReq,
+    ) -> Result<String> {
+        let local_versions = self.find_local_versions().await?;
+        if let Some(version) = highest_in_range(local_versions, range) {
+            return Ok(version);
+        }
+
+        let remote_versions = self
+            .find_remote_releases(false, |releases| {
+                highest_in_range(versions_of(releases), range).is_some()
+            })
+            .await?;
+        let remote_versions = versions_of(&remote_versions);
+        highest_in_range(remote_versions, range).ok_or_else(|| {
+            format!("No version of {} matching {} found", self.name(), range)
+                .into()
+        })
+    }
+
     pub async fn run<'b, I, S>(&self, args: I) -> Result<()>
     where
         S: AsRef<[u8]>,
         I: IntoIterator<Item = S>,
     {
-        if !self.is_installed().await? {
-            self.install(false).await?;
-        }
-
+        self.check_runnable()?;
+        self.install(false).await?;
         self.exec(args).await
     }
 
@@ -255,13 +516,21 @@ impl<'a> Tool<'a> {
         S: AsRef<[u8]>,
         I: IntoIterator<Item = S>,
     {
-        let tool_name = self.name();
+        let tool_name = self.binary_name();
         let bin = self.exec_path().await?;
         let bin = CString::new(bin.to_raw_bytes()).unwrap();
+        let default_args = self.default_args().await?;
         let args = args.into_iter().map(|x| CString::new(x.as_ref()).unwrap());
         let exec_args = iter::once(CString::new(tool_name).unwrap())
+            .chain(
+                default_args
+                    .iter()
+                    .map(|x| CString::new(x.as_str()).unwrap()),
+            )
             .chain(args)
             .collect::<Vec<_>>();
+        // only informs `toolbox cleanup`, so failing to record it is fine
+        self.record_use().await.ok();
         execve(&bin, &exec_args, &self.get_exec_env().await?)?;
         Ok(())
     }
@@ -271,24 +540,102 @@ impl<'a> Tool<'a> {
         S: AsRef<OsStr>,
         I: IntoIterator<Item = S>,
     {
-        if !self.is_installed().await? {
-            self.install(false).await?;
-        }
+        let mut command = self.base_command().await?;
+        command.args(self.default_args().await?).args(args);
+        Ok(command)
+    }
+
+    /// like `command`, but without the default arguments of the definition
+    /// and the config. Used for internal calls, which rely on the exact
+    /// arguments.
+    pub async fn plain_command<I, S>(&self, args: I) -> Result<Command>
+    where
+        S: AsRef<OsStr>,
+        I: IntoIterator<Item = S>,
+    {
+        let mut command = self.base_command().await?;
+        command.args(args);
+        Ok(command)
+    }
+
+    async fn base_command(&self) -> Result<Command> {
+        self.check_runnable()?;
+        self.install(false).await?;
 
-        let tool_name = self.name();
+        let tool_name = self.binary_name();
         let bin = self.exec_path().await?;
 
         let mut command = Command::new(&bin);
-        command.arg0(tool_name).args(args);
+        command.arg0(tool_name).env("PATH", self.path_env().await?);
+        for (name, value) in self.exec_env().await? {
+            if env::var_os(&name).is_none() {
+                command.env(name, value);
+            }
+        }
+        self.record_use().await.ok();
         Ok(command)
     }
 
+    /// environment variables of the tool and of its dependencies, as those
+    /// may be invoked through PATH. The ones of the tool win.
+    async fn exec_env(&self) -> Result<BTreeMap<String, String>> {
+        let mut env = BTreeMap::new();
+        for tool in self.toolbox.dependencies(self.name())? {
+            env.extend(tool.default_env().await?);
+        }
+        env.extend(self.default_env().await?);
+        Ok(env)
+    }
+
+    /// environment variables of the definition, overridden by the ones
+    /// configured for the tool in config.yaml
+    async fn default_env(&self) -> Result<Vec<(String, String)>> {
+        let mut env = self.definition.env.clone();
+        if let Some(settings) = self.toolbox.config().tool_settings(self.name())
+        {
+            env.extend(settings.env().clone());
+        }
+        let version = self.resolve_version().await?;
+        env.into_iter()
+            .map(|(name, value)| Ok((name, self.render(&value, &version)?)))
+            .collect()
+    }
+
+    /// arguments passed before the given ones. The ones configured for the
+    /// tool in config.yaml replace the ones of the definition. Additional
+    /// binaries get none.
+    async fn default_args(&self) -> Result<Vec<String>> {
+        if self.binary.is_some() {
+            return Ok(vec![]);
+        }
+        let args = self
+            .toolbox
+            .config()
+            .tool_settings(self.name())
+            .and_then(|settings| settings.args())
+            .unwrap_or(&self.definition.args);
+        let version = self.resolve_version().await?;
+        args.iter().map(|arg| self.render(arg, &version)).collect()
+    }
+
+    /// renders an `env` or `args` template. The variables of the project
+    /// can be used in addition to the ones of the definition.
+    fn render(&self, template: &str, version: &str) -> Result<String> {
+        let empty = HashMap::new();
+        let variables = match self.toolbox.project() {
+            Some(project) => project.variables(),
+            None => &empty,
+        };
+        self.definition
+            .replace_with(template, version, &self.platform(), variables)
+            .map_err(|e| format!("{}: {}: {}", self.name(), template, e).into())
+    }
+
     async fn build_path_env(&self) -> Result<OsString> {
-        let mut result = OsString::new();
-        for dep in self.definition.dependencies.iter() {
-            let tool = self
-                .toolbox
-                .tool_with_version(dep, vec![VersionRef::Local])?;
+        // the tool's own binaries, like the drivers of minikube
+        let mut result = self.exec_dir_path().await?.into_os_string();
+        result.push(":");
+        for tool in self.toolbox.dependencies(self.name())? {
             result.push(tool.exec_dir_path().await?);
             result.push(":");
         }
@@ -297,18 +644,27 @@ impl<'a> Tool<'a> {
         Ok(result)
     }
 
+    async fn path_env(&self) -> Result<OsString> {
+        let mut result = self.build_path_env().await?;
+        if let Some(path) = env::var_os("PATH") {
+            result.push(path);
+        }
+        Ok(result)
+    }
+
     async fn get_exec_env(&self) -> Result<Vec<CString>> {
-        let bin_dir_path = self.build_path_env().await?;
+        let path = self.path_env().await?;
+        let exec_env = self
+            .exec_env()
+            .await?
+            .into_iter()
+            .filter(|(name, _)| env::var_os(name).is_none())
+            .map(|(name, value)| (OsString::from(name), OsString::from(value)));
         let new_env = env::vars_os()
             .into_iter()
-            .map(|(name, value)| match name.to_str() {
-                Some("PATH") => {
-                    let mut new_value = bin_dir_path.clone();
-                    new_value.push(value);
-                    (name, new_value)
-                }
-                _ => (name, value),
-            })
+            .filter(|(name, _)| name != "PATH")
+            .chain(iter::once((OsString::from("PATH"), path)))
+            .chain(exec_env)
             .map(|(mut var, value)| {
                 var.push("=");
                 var.push(value);
@@ -322,12 +678,28 @@ impl<'a> Tool<'a> {
         self.toolbox.downloader()
     }
 
+    pub fn platform(&self) -> Platform {
+        self.toolbox.platform().clone()
+    }
+
+    /// fails for tools installed for another platform than the current one
+    fn check_runnable(&self) -> Result<()> {
+        let platform = self.toolbox.platform();
+        if platform.is_current() {
+            return Ok(());
+        }
+        Err(format!(
+            "{} is installed for {} and can't run on {}",
+            self.name(),
+            platform,
+            Platform::current()
+        )
+        .into())
+    }
+
     pub async fn find_latest_version(&self) -> Result<String> {
         let url = self.definition.upstream().version_url();
-        let response = self
-            .downloader()
-            .string(&url, &format!("{} check latest", self.name()))
-            .await?;
+        let response = self.downloader().cached_string(&url).await?;
         let latest_version = self
             .definition
             .upstream()
@@ -335,6 +707,114 @@ impl<'a> Tool<'a> {
         Ok(latest_version)
     }
 
+    /// finds a version matching the api server of the current context.
+    /// Prefers local versions of the same minor, then local versions one
+    /// minor newer or older, then the newest upstream version of the same
+    /// minor.
+    async fn find_cluster_version(&self) -> Result<String> {
+        if self.name() != "kubectl" {
+            return Err(format!(
+                "{}: `cluster` can only be used as version of kubectl",
+                self.name()
+            )
+            .into());
+        }
+        let (major, minor) = match cluster::server_version().await {
+            Ok(version) => version,
+            Err(e) => {
+                eprintln!(
+                    "Warning: {}, using the local or latest kubectl instead",
+                    e
+                );
+                return match self.find_local_version().await? {
+                    Some(version) => Ok(version),
+                    None => self.find_latest_version().await,
+                };
+            }
+        };
+        let local_versions = self.find_local_versions().await?;
+        if let Some(version) = highest_in_skew(local_versions, major, minor) {
+            return Ok(version);
+        }
+
+        let range = VersionReq::parse(&format!("~{}.{}", major, minor))?;
+        self.find_version_in_range(&range).await
+    }
+
+    /// lists up to `limit` versions available upstream, newest release
+    /// first. Upstreams that can't list their releases only report the
+    /// latest version.
+    pub async fn find_remote_versions(
+        &self,
+        prereleases: bool,
+        limit: Option<usize>,
+    ) -> Result<Vec<String>> {
+        let releases = self
+            .find_remote_releases(prereleases, |releases| {
+                matches!(limit, Some(limit) if releases.len() >= limit)
+            })
+            .await?;
+        let mut versions = versions_of(&releases);
+        if let Some(limit) = limit {
+            versions.truncate(limit);
+        }
+        Ok(versions)
+    }
+
+    /// reads pages of upstream releases, newest first, until `done` is
+    /// satisfied with the releases found so far or there are no more pages
+    pub async fn find_remote_releases<F>(
+        &self,
+        prereleases: bool,
+        done: F,
+    ) -> Result<Vec<Release>>
+    where
+        F: Fn(&[Release]) -> bool,
+    {
+        let upstream = self.definition.upstream();
+        let mut releases = vec![];
+        for page in 1..=MAX_VERSION_PAGES {
+            let url = match upstream.versions_url(page) {
+                Some(url) => url,
+                None if page == 1 => {
+                    return Ok(vec![Release {
+                        version: self.find_latest_version().await?,
+                        prerelease: false,
+                        published_at: None,
+                        notes: None,
+                    }])
+                }
+                None => break,
+            };
+            let response = self.downloader().cached_string(&url).await?;
+            let page = upstream.parse_versions_from_response(&response)?;
+            if page.is_empty() {
+                break;
+            }
+            releases.extend(
+                page.into_iter().filter(|r| prereleases || !r.prerelease),
+            );
+            if done(&releases) {
+                break;
+            }
+        }
+        Ok(releases)
+    }
+
+    /// the package url followed by the configured mirrors
+    pub fn package_urls(&self, version: &str) -> Result<Vec<String>> {
+        let mut urls =
+            vec![self.definition.package_url(version, &self.platform())?];
+        for mirror in self.toolbox.config().mirrors(self.name()) {
+            urls.push(self.definition.replace(
+                mirror,
+                version,
+                &self.platform(),
+            )?);
+        }
+        Ok(urls)
+    }
+
     pub async fn find_local_versions(&self) -> Result<Vec<String>> {
         let exec_dir = self.toolbox.exec_dir_path()?.join(self.name());
         let mut versions = vec![];
@@ -359,24 +839,16 @@ impl<'a> Tool<'a> {
     }
 
     pub async fn install(&self, force: bool) -> Result<bool> {
-        let mut result = self.real_install(force).await?;
-        for dep in self.definition.dependencies.iter() {
-            let tool = self.toolbox.tool_with_version(
-                dep,
-                vec![VersionRef::Local, VersionRef::Latest],
-            )?;
-            let dep_result = tool.real_install(force).await?;
-            result = result || dep_result;
+        let deps = self.toolbox.dependencies(self.name())?;
+        let (main, deps) = join!(
+            self.real_install(force),
+            join_all(deps.iter().map(|tool| tool.real_install(force)))
+        );
+        let mut result = main?;
+        for dep_result in deps {
+            result = dep_result? || result;
         }
         Ok(result)
-        //let (main, deps) = join!(
-        //    self.real_install(force),
-        //    join_all(self.definition.dependencies.iter().map(|dep| async {
-        //        let tool = self.toolbox.tool(dep)?;
-        //        Ok::<_, Error>(tool.real_install(false).await?)
-        //    }))
-        //);
-        //deps.into_iter().find(Result::is_err).unwrap_or(main)
     }
 
     async fn real_install(&self, force: bool) -> Result<bool> {
@@ -384,78 +856,192 @@ impl<'a> Tool<'a> {
             return Ok(false);
         }
         let version = self.resolve_version().await?;
-        let bin_path = self.exec_path().await?;
-        let temp_bin_path = bin_path.with_extension("part");
-        let url = self.definition.package_url(&version)?;
+        let exec_dir_path = self.exec_dir_path().await?;
+        let _guard = self.toolbox.install_lock(&exec_dir_path).await;
+        if self.is_installed().await? && !force {
+            return Ok(false);
+        }
+        self.install_into(&exec_dir_path, &version).await?;
+        Ok(true)
+    }
 
-        let mut stream = self
-            .downloader()
-            .stream(&url, &format!("{}-{}", self.name(), version))
-            .await?;
+    /// downloads, verifies and extracts `version` of the tool into `dir`.
+    /// Does not take the install lock.
+    pub async fn install_into(
+        &self,
+        exec_dir_path: &Path,
+        version: &str,
+    ) -> Result<()> {
+        let platform = self.platform();
+        let bin_path = exec_dir_path.join(self.name());
+        let temp_bin_path = part_path(&bin_path);
+        let binaries = self.definition.binaries_for(&platform);
+        if !binaries.is_empty() && self.definition.extract().is_none() {
+            return Err(format!(
+                "{}: binaries can only be installed from an archive",
+                self.name()
+            )
+            .into());
+        }
+        let urls = self.package_urls(version)?;
 
-        fs::create_dir_all(&self.exec_dir_path().await?).await?;
-        let mut file = File::create(&temp_bin_path).await?;
+        let expected_digest = match self.definition.checksum() {
+            Some(checksum) => Some(
+                checksum
+                    .digest(
+                        self.definition,
+                        version,
+                        &platform,
+                        self.downloader(),
+                        &urls,
+                    )
+                    .await?,
+            ),
+            None => None,
+        };
 
-        let extract_command = self.definition.extract_command(&version)?;
-        if extract_command.is_empty() {
-            while let Some(chunk) = stream.next().await {
-                file.write_all(&chunk?).await?;
+        let mut members = vec![];
+        let mut extractor = None;
+        if let Some(extract) = self.definition.extract() {
+            members.push((
+                self.definition
+                    .replace(extract.path(), version, &platform)?,
+                temp_bin_path.clone(),
+            ));
+            for binary in &binaries {
+                members.push((
+                    self.definition.replace(
+                        binary.path(),
+                        version,
+                        &platform,
+                    )?,
+                    part_path(&exec_dir_path.join(binary.name())),
+                ));
             }
+            // archives are extracted while they are downloaded, but only
+            // moved into place once the digest is verified
+            extractor = Some(StreamingExtractor::new(
+                extract.format(),
+                members.clone(),
+            ));
         } else {
-            let mut process = Command::new(&extract_command[0])
-                .args(&extract_command[1..])
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::inherit())
-                .spawn()?;
-            let (reader, writer) = tokio::join!(
-                async {
-                    let mut stdin = process.stdin.take().unwrap();
-                    while let Some(chunk) = stream.next().await {
-                        stdin.write_all(&chunk?).await?;
-                    }
-
-                    Ok::<_, Error>(())
-                },
-                async {
-                    let mut stdout = process.stdout.take().unwrap();
-                    let mut buffer = [0u8; 1024];
-                    while let Ok(n) = stdout.read(&mut buffer).await {
-                        if n == 0 {
-                            break;
-                        }
-                        file.write_all(&buffer[..n]).await?;
-                    }
-
-                    Ok::<_, Error>(())
+            members.push((String::new(), temp_bin_path.clone()));
+        }
+
+        fs::create_dir_all(exec_dir_path).await?;
+        // partial downloads are kept on failure so the next attempt can
+        // resume them
+        let package_path = with_suffix(&bin_path, ".pkg");
+        let label = format!("{}-{}", self.name(), version);
+        let download = match &mut extractor {
+            Some(extractor) => {
+                self.downloader()
+                    .resume_any_into(&urls, &package_path, &label, extractor)
+                    .await
+            }
+            None => {
+                self.downloader()
+                    .resume_any(&urls, &package_path, &label)
+                    .await
+            }
+        };
+        let verified = match download {
+            Ok(url) => {
+                let digest = checksum::file_digest(&package_path).await?;
+                check_digest(&digest, &expected_digest, &url)
+                    .map(|_| (url, digest))
+            }
+            Err(e) => Err(e),
+        };
+        let package = verified.as_ref().ok().cloned();
+        let result = match (verified, extractor) {
+            (Err(e), Some(extractor)) => {
+                extractor.abort().await;
+                Err(e)
+            }
+            (Err(e), None) => Err(e),
+            (Ok(_), Some(extractor)) => extractor.finish(&package_path).await,
+            (Ok(_), None) => {
+                let extract_command =
+                    self.definition.extract_command(version, &platform)?;
+                if extract_command.is_empty() {
+                    fs::rename(&package_path, &temp_bin_path)
+                        .await
+                        .map_err(Error::from)
+                } else {
+                    run_extract_command(
+                        &extract_command,
+                        &package_path,
+                        &temp_bin_path,
+                    )
+                    .await
                 }
-            );
-            let result = process.wait().await?;
-            if !result.success() {
-                return Err(format!(
-                    "Failed to extract binary: {:?}",
-                    result.code()
-                )
-                .into());
             }
+        };
+        if package_path.exists() {
+            fs::remove_file(&package_path).await?;
+        }
+        if let Err(e) = result {
+            for (_, dest) in &members {
+                if dest.exists() {
+                    fs::remove_file(dest).await?;
+                }
+            }
+            return Err(e);
+        }
 
-            reader?;
-            writer?;
+        for binary in binaries {
+            let path = exec_dir_path.join(binary.name());
+            make_executable(&part_path(&path), &path).await?;
+            if let Some(alias) = binary.alias() {
+                let alias_path = exec_dir_path.join(alias);
+                if alias_path.exists() {
+                    fs::remove_file(&alias_path).await?;
+                }
+                fs::symlink(binary.name(), &alias_path).await?;
+            }
         }
-        let mut permission = file.metadata().await?.permissions();
-        permission.set_mode(0o755);
-        fs::set_permissions(&temp_bin_path, permission).await?;
-        fs::rename(&temp_bin_path, &bin_path).await?;
+        make_executable(&temp_bin_path, &bin_path).await?;
+        if let Some((url, digest)) = package {
+            record_package(exec_dir_path, &url, &digest).await?;
+        }
+        Ok(())
+    }
 
-        Ok(true)
+    /// url and sha256 digest of the package the version was installed from.
+    /// `None` for versions installed before they were recorded.
+    pub async fn installed_package(&self) -> Result<Option<(String, String)>> {
+        let path = self.exec_dir_path().await?.join(PACKAGE_FILE_NAME);
+        let content = match fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(_) => return Ok(None),
+        };
+        Ok(content
+            .trim_end()
+            .split_once("  ")
+            .map(|(sha256, url)| (url.to_string(), sha256.to_string())))
+    }
+
+    /// sha256 digest of the installed binary
+    pub async fn digest(&self) -> Result<String> {
+        checksum::file_digest(&self.exec_path().await?).await
     }
 
+    /// whether the version and all of its binaries are installed
     pub async fn is_installed(&self) -> Result<bool> {
         match self.resolve_version().await {
             Ok(v) => v,
             Err(_) => return Ok(false),
         };
-        Ok(self.exec_path().await?.exists())
+        let exec_dir_path = self.exec_dir_path().await?;
+        if !exec_dir_path.join(self.name()).exists() {
+            return Ok(false);
+        }
+        Ok(self
+            .definition
+            .binaries_for(&self.platform())
+            .iter()
+            .all(|b| exec_dir_path.join(b.name()).exists()))
     }
 
     pub fn get_args(matches: &clap::ArgMatches) -> clap::Values<'_> {
@@ -484,11 +1070,154 @@ impl<'a> Tool<'a> {
         command
     }
 
+    async fn record_use(&self) -> Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = self.exec_dir_path().await?.join(LAST_USED_FILE_NAME);
+        fs::write(path, now.to_string()).await?;
+        Ok(())
+    }
+
+    /// when the version was executed the last time. Falls back to the time
+    /// of installation for versions that never ran.
+    pub async fn last_used(&self) -> Result<SystemTime> {
+        let exec_dir_path = self.exec_dir_path().await?;
+        let recorded =
+            fs::read_to_string(exec_dir_path.join(LAST_USED_FILE_NAME))
+                .await
+                .ok()
+                .and_then(|s| s.trim().parse().ok());
+        match recorded {
+            Some(secs) => Ok(UNIX_EPOCH + Duration::from_secs(secs)),
+            None => Ok(fs::metadata(&exec_dir_path).await?.modified()?),
+        }
+    }
+
+    /// bytes used by the installed version
+    pub async fn disk_usage(&self) -> Result<u64> {
+        dir_size(&self.exec_dir_path().await?).await
+    }
+
+    /// removes the version including its additional binaries
     pub async fn remove(self) -> Result<()> {
-        let bin_path = self.exec_path().await?;
-        if bin_path.exists() {
-            fs::remove_file(&bin_path).await?;
+        let exec_dir_path = self.exec_dir_path().await?;
+        if exec_dir_path.exists() {
+            fs::remove_dir_all(&exec_dir_path).await?;
         }
         Ok(())
     }
 }
+
+/// records that the version in `dir` was installed from the package at
+/// `url` with the digest `sha256`
+pub async fn record_package(dir: &Path, url: &str, sha256: &str) -> Result<()> {
+    let content = format!("{}  {}\n", sha256, url);
+    fs::write(dir.join(PACKAGE_FILE_NAME), content).await?;
+    Ok(())
+}
+
+/// total size of the files below `path`
+async fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = fs::symlink_metadata(entry.path()).await?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                size += metadata.len();
+            }
+        }
+    }
+    Ok(size)
+}
+
+fn versions_of(releases: &[Release]) -> Vec<String> {
+    releases.iter().map(|r| r.version.clone()).collect()
+}
+
+pub fn highest_in_range(
+    versions: Vec<String>,
+    range: &VersionReq,
+) -> Option<String> {
+    versions
+        .into_iter()
+        .filter_map(|v| parse_semver(&v).map(|semver| (semver, v)))
+        .filter(|(semver, _)| range.matches(semver))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, v)| v)
+}
+
+/// the highest of `versions` within the version skew kubectl supports for
+/// an api server of version `major.minor`. The same minor version is
+/// preferred over one newer, which is preferred over one older.
+pub fn highest_in_skew(
+    versions: Vec<String>,
+    major: u64,
+    minor: u64,
+) -> Option<String> {
+    let skew = [Some(minor), Some(minor + 1), minor.checked_sub(1)];
+    skew.into_iter().flatten().find_map(|minor| {
+        let range = VersionReq::parse(&format!("~{}.{}", major, minor)).ok()?;
+        highest_in_range(versions.clone(), &range)
+    })
+}
+
+/// where `path` is written to before it is complete
+fn part_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".part")
+}
+
+/// appends `suffix` to `path`. Unlike `with_extension`, this keeps names
+/// containing dots intact.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// marks `temp_path` executable and moves it to `path`
+async fn make_executable(temp_path: &Path, path: &Path) -> Result<()> {
+    let mut permission = fs::metadata(temp_path).await?.permissions();
+    permission.set_mode(0o755);
+    fs::set_permissions(temp_path, permission).await?;
+    fs::rename(temp_path, path).await?;
+    Ok(())
+}
+
+fn check_digest(
+    digest: &str,
+    expected_digest: &Option<String>,
+    url: &str,
+) -> Result<()> {
+    match expected_digest {
+        Some(expected_digest) if digest != expected_digest => Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            url, expected_digest, digest
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// pipes the package through the `extract_command` of a tool
+async fn run_extract_command(
+    extract_command: &[String],
+    package_path: &Path,
+    dest: &Path,
+) -> Result<()> {
+    let result = Command::new(&extract_command[0])
+        .args(&extract_command[1..])
+        .stdin(std::fs::File::open(package_path)?)
+        .stdout(std::fs::File::create(dest)?)
+        .stderr(Stdio::inherit())
+        .status()
+        .await?;
+    if !result.success() {
+        return Err(
+            format!("Failed to extract binary: {:?}", result.code()).into()
+        );
+    }
+    Ok(())
+}
@@ -1,23 +1,57 @@
 use serde::Deserialize;
 
-use super::Upstream;
+use super::{Upstream, PAGE_SIZE};
 use crate::result::Result;
 
 #[derive(Deserialize, Clone)]
-#[serde(rename_all = "snake_case")]
+#[serde(try_from = "RawGithubReleaseUpstream")]
 pub struct GithubReleaseUpstream {
     repo: String,
-    #[serde(flatten)]
     source: PackageSource,
 }
 
-#[derive(Deserialize, Clone)]
-#[serde(rename_all = "snake_case")]
+#[derive(Clone)]
 pub enum PackageSource {
     File(String),
     PackageUrl(String),
 }
 
+/// `GithubReleaseUpstream` as written in the repository. serde can't deny
+/// unknown fields of flattened enums, so `file` and `package_url` are
+/// checked by hand.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+struct RawGithubReleaseUpstream {
+    repo: String,
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    package_url: Option<String>,
+}
+
+impl TryFrom<RawGithubReleaseUpstream> for GithubReleaseUpstream {
+    type Error = String;
+
+    fn try_from(
+        raw: RawGithubReleaseUpstream,
+    ) -> std::result::Result<Self, Self::Error> {
+        let source = match (raw.file, raw.package_url) {
+            (Some(file), None) => PackageSource::File(file),
+            (None, Some(url)) => PackageSource::PackageUrl(url),
+            _ => {
+                return Err(format!(
+                    "{}: set exactly one of `file` and `package_url`",
+                    raw.repo
+                ))
+            }
+        };
+        Ok(Self {
+            repo: raw.repo,
+            source,
+        })
+    }
+}
+
 impl Upstream for &GithubReleaseUpstream {
     fn version_url(&self) -> String {
         format!("https://api.github.com/repos/{}/releases/latest", self.repo)
@@ -43,4 +77,11 @@ impl Upstream for &GithubReleaseUpstream {
             .ok_or_else(|| format!("Malformed response: {}", response))?;
         Ok(tag_name.to_string())
     }
+
+    fn versions_url(&self, page: usize) -> Option<String> {
+        Some(format!(
+            "https://api.github.com/repos/{}/releases?per_page={}&page={}",
+            self.repo, PAGE_SIZE, page
+        ))
+    }
 }
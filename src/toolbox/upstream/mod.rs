@@ -6,10 +6,77 @@ pub use github_release::GithubReleaseUpstream;
 use serde::Deserialize;
 pub use simple::SimpleUpstream;
 
+/// releases requested per page from paginated upstreams
+pub static PAGE_SIZE: usize = 100;
+
 pub trait Upstream {
     fn version_url(&self) -> String;
     fn package_url(&self) -> String;
     fn parse_version_from_response(&self, response: &str) -> Result<String>;
+    /// url of the `page`th page listing releases, newest first. Pages
+    /// start at 1. None if the upstream can't list its releases or has no
+    /// such page.
+    fn versions_url(&self, _page: usize) -> Option<String> {
+        None
+    }
+    fn parse_versions_from_response(
+        &self,
+        response: &str,
+    ) -> Result<Vec<Release>> {
+        parse_release_list(response)
+    }
+}
+
+/// a release listed by an upstream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    pub prerelease: bool,
+    /// RFC 3339 timestamp, if the upstream reports one
+    pub published_at: Option<String>,
+    /// release notes, if the upstream reports them
+    pub notes: Option<String>,
+}
+
+/// parses a list of releases. This is either a GitHub releases response, a
+/// yaml or json list of versions or a text file with one version per line.
+/// Drafts are left out.
+pub fn parse_release_list(response: &str) -> Result<Vec<Release>> {
+    let releases = match serde_yaml::from_str::<serde_yaml::Value>(response) {
+        Ok(serde_yaml::Value::Sequence(releases)) => releases,
+        Ok(serde_yaml::Value::Mapping(_)) => {
+            return Err(format!("Malformed response: {}", response).into())
+        }
+        _ => {
+            return Ok(response
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(|l| Release {
+                    version: l.to_string(),
+                    prerelease: l.contains('-'),
+                    published_at: None,
+                    notes: None,
+                })
+                .collect())
+        }
+    };
+    Ok(releases
+        .iter()
+        .filter(|r| !r["draft"].as_bool().unwrap_or(false))
+        .filter_map(|r| {
+            let version = r.as_str().or_else(|| r["tag_name"].as_str())?;
+            let prerelease = r["prerelease"]
+                .as_bool()
+                .unwrap_or_else(|| version.contains('-'));
+            Some(Release {
+                version: version.to_string(),
+                prerelease,
+                published_at: r["published_at"].as_str().map(String::from),
+                notes: r["body"].as_str().map(String::from),
+            })
+        })
+        .collect())
 }
 
 #[derive(Deserialize, Clone)]
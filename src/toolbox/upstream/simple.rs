@@ -3,11 +3,18 @@ use serde::Deserialize;
 use super::Upstream;
 use crate::result::Result;
 
+static PAGE_PLACEHOLDER: &str = "{{page}}";
+
 #[derive(Deserialize, Clone)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct SimpleUpstream {
     version_url: String,
     package_url: String,
+    /// url listing all versions, see `parse_release_list` for the formats
+    /// understood. `{{page}}` is replaced with the page number for
+    /// paginated lists.
+    #[serde(default)]
+    versions_url: Option<String>,
 }
 
 impl Upstream for &SimpleUpstream {
@@ -22,4 +29,15 @@ impl Upstream for &SimpleUpstream {
     fn parse_version_from_response(&self, response: &str) -> Result<String> {
         Ok(response.trim().to_string())
     }
+
+    fn versions_url(&self, page: usize) -> Option<String> {
+        let url = self.versions_url.as_ref()?;
+        if url.contains(PAGE_PLACEHOLDER) {
+            Some(url.replace(PAGE_PLACEHOLDER, &page.to_string()))
+        } else if page == 1 {
+            Some(url.clone())
+        } else {
+            None
+        }
+    }
 }
@@ -0,0 +1,39 @@
+use std::fs;
+
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::VersionRef;
+use neatkube::toolbox::Toolbox;
+
+mod common;
+
+#[tokio::test]
+async fn test_active_version() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    let tool_dir = tempdir.path().join("exec/helm");
+    for version in ["v3.10.0", "v3.11.2"] {
+        common::install_fake(tempdir.path(), "helm", version)?;
+    }
+
+    let toolbox = Toolbox::create(Platform::current()).await?;
+    let tool = toolbox.tool_with_version("helm", [VersionRef::Local])?;
+    assert_eq!(tool.resolve_version().await?, "v3.11.2");
+
+    tool.set_active_version(Some("v3.10.0")).await?;
+    assert_eq!(fs::read_to_string(tool_dir.join(".active"))?, "v3.10.0");
+    assert_eq!(tool.find_local_version().await?.unwrap(), "v3.10.0");
+    // the versions of a tool are not confused with its active file
+    assert_eq!(tool.find_local_versions().await?, ["v3.10.0", "v3.11.2"]);
+
+    // an active version that was removed falls back to the highest one
+    fs::remove_dir_all(tool_dir.join("v3.10.0"))?;
+    assert_eq!(tool.find_local_version().await?.unwrap(), "v3.11.2");
+
+    // `toolbox use --latest`
+    tool.set_active_version(None).await?;
+    assert!(!tool_dir.join(".active").exists());
+    assert_eq!(tool.find_local_version().await?.unwrap(), "v3.11.2");
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::os::unix::fs::symlink;
+
+use neatkube::bundle::{self, BundledTool, Manifest};
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::repository::{self, Repository};
+use neatkube::toolbox::tool::LAST_USED_FILE_NAME;
+use neatkube::toolbox::Toolbox;
+
+#[test]
+fn test_bundle_roundtrip() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let exec_dir = tempdir.path().join("exec");
+    let version_dir = exec_dir.join("helm").join("v3.11.2");
+    fs::create_dir_all(&version_dir)?;
+    fs::write(version_dir.join("helm"), "helm")?;
+    fs::write(version_dir.join(LAST_USED_FILE_NAME), "")?;
+    symlink("helm", version_dir.join("kubectl-helm"))?;
+
+    let tool = BundledTool::new(
+        "helm".to_string(),
+        "v3.11.2".to_string(),
+        "https://get.helm.sh/helm-v3.11.2-linux-amd64.tar.gz".to_string(),
+        Some("0e1f".to_string()),
+        true,
+        &version_dir,
+    )
+    .map_err(|e| e as Error)?;
+    let manifest = Manifest::new("linux-amd64".to_string(), vec![tool]);
+    let archive = tempdir.path().join("tools.tar");
+    bundle::write(&archive, &exec_dir, &manifest, b"tools: []")
+        .map_err(|e| e as Error)?;
+
+    let dir = tempdir.path().join("unpacked");
+    let manifest = bundle::unpack(&archive, &dir).map_err(|e| e as Error)?;
+    assert_eq!(manifest.platform(), "linux-amd64");
+    let tool = &manifest.tools()[0];
+    assert_eq!(tool.sha256(), Some("0e1f"));
+    assert!(tool.active());
+    assert_eq!(fs::read_to_string(tool.dir_in(&dir).join("helm"))?, "helm");
+    assert!(tool.dir_in(&dir).join("kubectl-helm").is_symlink());
+    assert!(!tool.dir_in(&dir).join(LAST_USED_FILE_NAME).exists());
+
+    // a binary replaced after the export fails the check
+    fs::write(version_dir.join("helm"), "evil")?;
+    bundle::write(&archive, &exec_dir, &manifest, b"tools: []")
+        .map_err(|e| e as Error)?;
+    let err = bundle::unpack(&archive, &tempdir.path().join("tampered"))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("checksum mismatch for helm"));
+    Ok(())
+}
+
+#[test]
+fn test_malicious_manifest() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    for (name, version, file) in [
+        ("helm", "..", "helm"),
+        (".", "v3.11.2", "helm"),
+        ("", "v3.11.2", "helm"),
+        ("helm", "v3.11.2", "../helm"),
+        ("helm", "v3.11.2", "..\\helm"),
+    ] {
+        let manifest = format!(
+            "platform: linux-amd64\n\
+            tools:\n\
+            - name: \"{}\"\n  \
+              version: \"{}\"\n  \
+              url: https://example.com/helm.tar.gz\n  \
+              files: {{\"{}\": \"0e1f\"}}\n",
+            name,
+            version,
+            file.replace('\\', "\\\\")
+        );
+        let archive = tempdir.path().join("evil.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive)?);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, "bundle.yaml", manifest.as_bytes())?;
+        builder.finish()?;
+        drop(builder);
+
+        let dir = tempdir.path().join("unpacked");
+        let err = bundle::unpack(&archive, &dir).err().unwrap();
+        assert!(err.to_string().contains("invalid name"), "{}", err);
+        fs::remove_dir_all(&dir)?;
+    }
+    Ok(())
+}
+
+fn repository(tools: &[(&str, &str)]) -> String {
+    let mut yaml =
+        "default: {with_subcommand: a, no_subcommand: b}\n".to_string();
+    yaml.push_str("tools:\n");
+    for (name, description) in tools {
+        yaml.push_str(&format!(
+            "  - name: {}\n    description: {}\n    upstream:\n",
+            name, description
+        ));
+        yaml.push_str("      github_release: {repo: a/b, file: c}\n");
+    }
+    yaml
+}
+
+#[test]
+fn test_merge_repository() -> Result<(), Error> {
+    let base = repository(&[("helm", "built-in")]);
+    let extra = repository(&[("helm", "imported"), ("stern", "imported")]);
+    let merged = repository::merge(base.as_bytes(), extra.as_bytes())?;
+    let repository = Repository::from_slice(&merged)?;
+    let tools: Vec<_> = repository
+        .tools()
+        .iter()
+        .map(|t| (t.name(), t.description()))
+        .collect();
+    assert_eq!(tools, [("helm", "built-in"), ("stern", "imported")]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_broken_repository_is_ignored() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    let dir = Toolbox::repositories_dir_path()?;
+    fs::create_dir_all(&dir)?;
+    // a key only a newer nk knows
+    let newer = repository(&[("newer", "imported")])
+        .replace("    upstream:", "    sandbox: true\n    upstream:");
+    fs::write(dir.join("a.yaml"), newer)?;
+    fs::write(dir.join("b.yaml"), "tools: [")?;
+    fs::write(dir.join("c.yaml"), repository(&[("mytool", "imported")]))?;
+
+    let toolbox = Toolbox::create(Platform::current()).await?;
+    assert!(toolbox.tool("newer").is_err());
+    assert!(toolbox.tool("mytool").is_ok());
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::sync::{Arc, Mutex};
+
+use neatkube::download::Downloader;
+use neatkube::error::Error;
+
+mod common;
+
+#[tokio::test]
+async fn test_cached_string() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CACHE_TTL", "0");
+    let requests = Arc::new(Mutex::new(vec![]));
+    let seen = requests.clone();
+    let url = common::serve(move |request| {
+        let etag = request.header("If-None-Match").map(String::from);
+        let response = match etag.as_deref() {
+            Some("\"v1\"") => common::response("304 Not Modified", &[], b""),
+            _ => common::response("200 OK", &[("ETag", "\"v1\"")], b"v1.0.0"),
+        };
+        seen.lock().unwrap().push(etag);
+        response
+    });
+
+    let downloader = Downloader::default();
+    assert_eq!(downloader.cached_string(&url).await?, "v1.0.0");
+    // expired at once, so it is revalidated
+    assert_eq!(downloader.cached_string(&url).await?, "v1.0.0");
+    assert_eq!(
+        *requests.lock().unwrap(),
+        [None, Some("\"v1\"".to_string())]
+    );
+
+    // offline, the cache is used regardless of its age
+    downloader.set_offline(true);
+    assert_eq!(downloader.cached_string(&url).await?, "v1.0.0");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    let uncached = format!("{}/other", url);
+    let error = downloader.cached_string(&uncached).await.unwrap_err();
+    assert!(error.to_string().starts_with("Offline mode"), "{}", error);
+    Ok(())
+}
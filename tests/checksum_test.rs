@@ -0,0 +1,55 @@
+use neatkube::error::Error;
+use neatkube::toolbox::checksum::{
+    mirrored_urls, parse_manifest, parse_sidecar,
+};
+
+static DIGEST: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+#[test]
+fn test_parse_sidecar() -> Result<(), Error> {
+    assert_eq!(parse_sidecar(DIGEST)?, DIGEST);
+    let content = format!("{}  helm-v3.11.2-linux-amd64.tar.gz\n", DIGEST);
+    assert_eq!(parse_sidecar(&content)?, DIGEST);
+    assert!(parse_sidecar("\n").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_parse_manifest() -> Result<(), Error> {
+    let content = format!(
+        "{}  stern_1.24.0_darwin_amd64.tar.gz\n{} *stern_1.24.0_linux_amd64.tar.gz\n",
+        "0".repeat(64),
+        DIGEST
+    );
+    assert_eq!(
+        parse_manifest(&content, "stern_1.24.0_linux_amd64.tar.gz")?,
+        DIGEST
+    );
+    assert!(
+        parse_manifest(&content, "stern_1.24.0_linux_arm64.tar.gz").is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_mirrored_urls() {
+    let package_urls = [
+        "https://get.helm.sh/helm-v3.11.2-linux-amd64.tar.gz".to_string(),
+        "https://mirror.example.com/helm/v3.11.2/helm.tar.gz".to_string(),
+    ];
+    assert_eq!(
+        mirrored_urls(
+            "https://get.helm.sh/helm-v3.11.2-linux-amd64.tar.gz.sha256sum",
+            &package_urls
+        ),
+        [
+            "https://get.helm.sh/helm-v3.11.2-linux-amd64.tar.gz.sha256sum",
+            "https://mirror.example.com/helm/v3.11.2/\
+            helm-v3.11.2-linux-amd64.tar.gz.sha256sum",
+        ]
+    );
+    // only next to the package the layout of the mirror is known
+    let url = "https://checksums.example.com/helm.sha256sum";
+    assert_eq!(mirrored_urls(url, &package_urls), [url]);
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use neatkube::cmd::toolbox::is_expired;
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::{VersionRef, LAST_USED_FILE_NAME};
+use neatkube::toolbox::Toolbox;
+
+mod common;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[test]
+fn test_is_expired() {
+    // the newest version is kept by the default of `--keep 1`
+    assert!(!is_expired(0, 90 * DAY, 1, None));
+    assert!(is_expired(1, DAY, 1, None));
+    // `--used-within` keeps old versions beyond `--keep`
+    assert!(!is_expired(3, DAY, 1, Some(7)));
+    assert!(is_expired(3, 8 * DAY, 1, Some(7)));
+    // and doesn't remove versions kept by `--keep`
+    assert!(!is_expired(0, 90 * DAY, 1, Some(7)));
+    // with `--keep 0` only the use counts
+    assert!(is_expired(0, 8 * DAY, 0, Some(7)));
+    assert!(!is_expired(0, DAY, 0, Some(7)));
+}
+
+#[tokio::test]
+async fn test_command_records_use() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    let version_dir = common::install_fake(tempdir.path(), "helm", "v3.11.2")?;
+
+    let toolbox = Toolbox::create(Platform::current()).await?;
+    let tool = toolbox.tool_with_version(
+        "helm",
+        [VersionRef::Specific("v3.11.2".to_string())],
+    )?;
+    assert!(!version_dir.join(LAST_USED_FILE_NAME).exists());
+    tool.command(["version"]).await?;
+    assert!(version_dir.join(LAST_USED_FILE_NAME).exists());
+    assert!(tool.last_used().await?.elapsed()? < DAY);
+    Ok(())
+}
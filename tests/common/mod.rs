@@ -0,0 +1,133 @@
+// shared by several test crates, each using only a part of it
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpListener,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// head of a request received by `serve`
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// serves http on a random local port from a background thread and
+/// returns its base url. `handler` returns the raw response, see
+/// `response`.
+pub fn serve<F>(handler: F) -> String
+where
+    F: Fn(&Request) -> Vec<u8> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let mut parts = line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+            let mut headers = vec![];
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                match line.trim_end().split_once(':') {
+                    Some((name, value)) => headers
+                        .push((name.to_string(), value.trim().to_string())),
+                    None => break,
+                }
+            }
+            let request = Request {
+                method,
+                path,
+                headers,
+            };
+            stream.write_all(&handler(&request)).ok();
+        }
+    });
+    url
+}
+
+/// a raw http response that closes the connection
+pub fn response(
+    status: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Vec<u8> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let mut response = head.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+/// serves `body` at every path, honouring `Range: bytes=N-` requests
+pub fn serve_ranges(body: &'static [u8]) -> String {
+    serve(move |request| {
+        let start = request
+            .header("range")
+            .and_then(|r| r.strip_prefix("bytes="))
+            .and_then(|r| r.strip_suffix('-'))
+            .and_then(|r| r.parse::<usize>().ok());
+        match start {
+            Some(start) => response(
+                "206 Partial Content",
+                &[(
+                    "Content-Range",
+                    &format!(
+                        "bytes {}-{}/{}",
+                        start,
+                        body.len() - 1,
+                        body.len()
+                    ),
+                )],
+                &body[start..],
+            ),
+            None => response("200 OK", &[], body),
+        }
+    })
+}
+
+/// installs an executable shell script as `version` of `tool` into the exec
+/// dir of `data_dir` and returns its version dir
+pub fn install_fake(
+    data_dir: &Path,
+    tool: &str,
+    version: &str,
+) -> io::Result<PathBuf> {
+    let version_dir = data_dir.join("exec").join(tool).join(version);
+    fs::create_dir_all(&version_dir)?;
+    let bin = version_dir.join(tool);
+    fs::write(&bin, "#!/bin/sh\n")?;
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755))?;
+    Ok(version_dir)
+}
@@ -0,0 +1,143 @@
+use std::fs;
+
+use neatkube::config::Config;
+use neatkube::download::{split_certificates, Downloader};
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::VersionRef;
+use neatkube::toolbox::Toolbox;
+
+mod common;
+
+static DIGEST: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+#[test]
+fn test_rewrite_rule() -> Result<(), Error> {
+    let config = Config::from_slice(
+        b"rewrites:\n\
+        - prefix: https://github.com/\n  \
+          replacement: https://artifactory.example.com/github/\n",
+    )?;
+    let rule = &config.rewrites()[0];
+    assert_eq!(
+        rule.apply("https://github.com/helm/helm").as_deref(),
+        Some("https://artifactory.example.com/github/helm/helm")
+    );
+    assert_eq!(rule.apply("https://get.helm.sh/helm.tar.gz"), None);
+    Ok(())
+}
+
+#[test]
+fn test_unknown_keys() {
+    assert!(Config::from_slice(b"mirror: {}\n").is_err());
+    let rewrites = b"rewrites: [{prefix: a, replace: b}]\n";
+    assert!(Config::from_slice(rewrites).is_err());
+    assert!(Config::from_slice(b"tools: {helm: {arg: []}}\n").is_err());
+}
+
+#[test]
+fn test_split_certificates() {
+    let pem = |name: &str| {
+        format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----",
+            name
+        )
+    };
+    let bundle = format!("# corporate CAs\n{}\n{}\n", pem("a"), pem("b"));
+    let certificates = split_certificates(&bundle);
+    assert_eq!(certificates.len(), 2);
+    assert!(certificates[0].ends_with(&pem("a")));
+    assert_eq!(certificates[1].trim(), pem("b"));
+    assert!(split_certificates("no certificates\n").is_empty());
+}
+
+#[test]
+fn test_missing_ca_certificates() -> Result<(), Error> {
+    let config = Config::from_slice(b"ca_certificates: [/nonexistent.pem]")?;
+    let error = Downloader::new(&config).err().unwrap();
+    assert!(
+        error.to_string().starts_with("/nonexistent.pem: "),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy() -> Result<(), Error> {
+    let proxy = common::serve(|request| {
+        // a proxy receives the absolute url
+        let body = request.path.clone().into_bytes();
+        common::response("200 OK", &[], &body)
+    });
+    let config = format!("proxy: {}\n", proxy);
+    let config = Config::from_slice(config.as_bytes())?;
+    let downloader = Downloader::new(&config)?;
+    let body = downloader
+        .string("http://nk.invalid/latest", "test")
+        .await?;
+    assert_eq!(body, "http://nk.invalid/latest");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mirrors() -> Result<(), Error> {
+    let url = common::serve(|request| match request.path.as_str() {
+        "/mirror/helm-v3.11.2-linux-amd64.tar.gz.sha256sum" => {
+            common::response("200 OK", &[], DIGEST.as_bytes())
+        }
+        _ => common::response("404 Not Found", &[], b""),
+    });
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    // the upstream is unreachable, like on an air-gapped machine
+    let template = "helm-{{version}}-{{os}}-{{arch}}.tar.gz";
+    let config = format!(
+        "rewrites:\n\
+        - prefix: https://get.helm.sh/\n  \
+          replacement: {url}/upstream/\n\
+        mirrors:\n  \
+          helm:\n  \
+          - \"{url}/mirror/{template}\"\n  \
+          - \"{url}/second/helm.tar.gz\"\n",
+        url = url,
+        template = template
+    );
+    fs::write(tempdir.path().join("config.yaml"), config)?;
+
+    let platform: Platform = "linux-amd64".parse()?;
+    let toolbox = Toolbox::create(platform).await?;
+    let tool = toolbox.tool_with_version(
+        "helm",
+        [VersionRef::Specific("v3.11.2".to_string())],
+    )?;
+    let urls = tool.package_urls("v3.11.2")?;
+    assert_eq!(
+        urls,
+        [
+            format!(
+                "https://get.helm.sh/{}",
+                "helm-v3.11.2-linux-amd64.tar.gz"
+            ),
+            format!("{}/mirror/helm-v3.11.2-linux-amd64.tar.gz", url),
+            format!("{}/second/helm.tar.gz", url),
+        ]
+    );
+
+    let digest = tool
+        .definition
+        .checksum()
+        .unwrap()
+        .digest(
+            tool.definition,
+            "v3.11.2",
+            toolbox.platform(),
+            toolbox.downloader(),
+            &urls,
+        )
+        .await?;
+    assert_eq!(digest, DIGEST);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use neatkube::toolbox::dependency;
+use neatkube::toolbox::tool::ToolDefinition;
+
+fn definitions(dependencies: &[(&str, &[&str])]) -> Vec<ToolDefinition> {
+    dependencies
+        .iter()
+        .map(|(name, deps)| {
+            let yaml = format!(
+                "name: {}\n\
+                description: ''\n\
+                upstream:\n  simple:\n    version_url: ''\n    package_url: ''\n\
+                dependencies: [{}]\n",
+                name,
+                deps.join(", ")
+            );
+            serde_yaml::from_str(&yaml).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_resolve_order() {
+    let tools = definitions(&[
+        ("helmfile", &["helm", "kustomize"]),
+        ("helm", &["kubectl"]),
+        ("kustomize", &["kubectl@~1.24"]),
+        ("kubectl", &[]),
+    ]);
+    let closure = dependency::resolve(&tools, "helmfile").unwrap();
+    assert_eq!(closure, vec!["kubectl@~1.24", "helm", "kustomize"]);
+    assert!(dependency::resolve(&tools, "kubectl").unwrap().is_empty());
+}
+
+#[test]
+fn test_resolve_errors() {
+    let tools = definitions(&[("a", &["b"]), ("b", &["c@1.0"]), ("c", &["a"])]);
+    let err = dependency::resolve(&tools, "a").unwrap_err();
+    assert_eq!(err.to_string(), "Dependency cycle: a -> b -> c -> a");
+
+    let tools = definitions(&[
+        ("helmfile", &["helm", "kustomize"]),
+        ("helm", &["kubectl@~1.25"]),
+        ("kustomize", &["kubectl@~1.24"]),
+        ("kubectl", &[]),
+    ]);
+    let err = dependency::resolve(&tools, "helmfile").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Conflicting constraints kubectl@~1.25 and kubectl@~1.24 required by \
+        kustomize"
+    );
+
+    let tools = definitions(&[("a", &["missing"])]);
+    let err = dependency::resolve(&tools, "a").unwrap_err();
+    assert_eq!(err.to_string(), "Unknown dependency missing of a");
+}
@@ -0,0 +1,25 @@
+use neatkube::cmd::doctor::elf_arch;
+use neatkube::toolbox::platform::Platform;
+
+#[test]
+fn test_elf_arch() {
+    let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    assert_eq!(elf_arch(&exe[..64]), Some(Platform::current().arch()));
+
+    let mut header = vec![0u8; 64];
+    header[..4].copy_from_slice(b"\x7fELF");
+    header[5] = 1;
+    header[18] = 0xb7;
+    assert_eq!(elf_arch(&header), Some("arm64"));
+    // named like the platforms `Platform::parse` accepts
+    header[18] = 0x15;
+    assert_eq!(elf_arch(&header), Some("ppc64le"));
+    assert_eq!(
+        Platform::parse("linux", "powerpc64le").unwrap().arch(),
+        "ppc64le"
+    );
+    header[18] = 0x99;
+    assert_eq!(elf_arch(&header), Some("unknown"));
+
+    assert_eq!(elf_arch(b"#!/bin/sh\necho hello world\n"), None);
+}
@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use neatkube::download::{parse_content_range, Downloader};
+use neatkube::error::Error;
+use reqwest::StatusCode;
+
+mod common;
+
+static BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+#[test]
+fn test_parse_content_range() {
+    assert_eq!(
+        parse_content_range("bytes 100-199/200"),
+        Some((100, Some(200)))
+    );
+    assert_eq!(parse_content_range("bytes 0-99/*"), Some((0, None)));
+    assert_eq!(parse_content_range("bytes */200"), Some((0, Some(200))));
+    assert_eq!(parse_content_range("items 0-1/2"), None);
+}
+
+#[tokio::test]
+async fn test_resume() -> Result<(), Error> {
+    let url = common::serve_ranges(BODY);
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("tool.pkg");
+    std::fs::write(&path, &BODY[..10])?;
+
+    Downloader::default().resume(&url, &path, "tool").await?;
+    assert_eq!(std::fs::read(&path)?, BODY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resume_any_keeps_partials_apart() -> Result<(), Error> {
+    let primary =
+        common::serve(|_| common::response("404 Not Found", &[], b""));
+    let mirror = common::serve_ranges(BODY);
+    let urls = [primary, mirror];
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("tool.pkg");
+    // left behind by an interrupted download from the primary
+    std::fs::write(Downloader::part_path(&path, &urls[0]), "partial")?;
+
+    let url = Downloader::default()
+        .resume_any(&urls, &path, "tool")
+        .await?;
+    assert_eq!(url, urls[1]);
+    assert_eq!(std::fs::read(&path)?, BODY);
+    assert!(!Downloader::part_path(&path, &urls[0]).exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limit_is_not_retried() -> Result<(), Error> {
+    let reset = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 3600;
+    let url = common::serve(move |_| {
+        common::response(
+            "403 Forbidden",
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", &reset.to_string()),
+            ],
+            b"",
+        )
+    });
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("tool.pkg");
+
+    let started = Instant::now();
+    let result = Downloader::default().resume(&url, &path, "tool").await;
+    let err = result.err().unwrap().to_string();
+    assert!(err.starts_with("Rate limit exceeded"), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limit_without_reset_fails_fast() -> Result<(), Error> {
+    let url =
+        common::serve(|_| common::response("429 Too Many Requests", &[], b""));
+
+    let started = Instant::now();
+    let result = Downloader::default().string(&url, "tool").await;
+    let err = result.err().unwrap().to_string();
+    assert!(err.starts_with("Rate limit exceeded for"), "{}", err);
+    assert!(!err.contains("resets in"), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_status_does_not_download() -> Result<(), Error> {
+    let methods = Arc::new(Mutex::new(vec![]));
+    let seen = methods.clone();
+    let url = common::serve(move |request| {
+        seen.lock().unwrap().push(request.method.clone());
+        match request.method.as_str() {
+            "HEAD" => common::response("200 OK", &[], b""),
+            _ => common::response("500 Internal Server Error", &[], b""),
+        }
+    });
+    let status = Downloader::default().status(&url).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(*methods.lock().unwrap(), ["HEAD"]);
+
+    // servers rejecting HEAD are asked for a single byte
+    let url = common::serve(|request| match request.header("range") {
+        _ if request.method == "HEAD" => {
+            common::response("405 Method Not Allowed", &[], b"")
+        }
+        Some("bytes=0-0") => common::response(
+            "206 Partial Content",
+            &[("Content-Range", "bytes 0-0/36")],
+            &BODY[..1],
+        ),
+        _ => common::response("200 OK", &[], BODY),
+    });
+    let status = Downloader::default().status(&url).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_download_removes_partial() -> Result<(), Error> {
+    let url = common::serve(|_| common::response("404 Not Found", &[], b""));
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("tool.pkg");
+    std::fs::write(&path, &BODY[..10])?;
+
+    assert!(Downloader::default()
+        .resume(&url, &path, "tool")
+        .await
+        .is_err());
+    assert!(!path.exists());
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::io::{self, Write};
+
+use flate2::{write::GzEncoder, Compression};
+use neatkube::download::DownloadSink;
+use neatkube::error::Error;
+use neatkube::toolbox::extract::{extract, ArchiveFormat, StreamingExtractor};
+
+#[test]
+fn test_extract_tar_gz() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let archive_path = tempdir.path().join("tool.tar.gz");
+    let dest = tempdir.path().join("tool");
+
+    let encoder = GzEncoder::new(
+        fs::File::create(&archive_path)?,
+        Compression::default(),
+    );
+    let mut builder = tar::Builder::new(encoder);
+    let content = b"#!/bin/sh\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder.append_data(&mut header, "./linux-amd64/tool", &content[..])?;
+    builder.into_inner()?.finish()?;
+
+    let members = [("linux-amd64/tool".to_string(), dest.clone())];
+    extract(ArchiveFormat::TarGz, &archive_path, &members)
+        .map_err(|e| e as Error)?;
+    assert_eq!(fs::read(&dest)?, content);
+
+    let members = [("missing".to_string(), dest)];
+    assert!(extract(ArchiveFormat::TarGz, &archive_path, &members).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_extract_multiple_members() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let archive_path = tempdir.path().join("minikube.tar.gz");
+    let encoder = GzEncoder::new(
+        fs::File::create(&archive_path)?,
+        Compression::default(),
+    );
+    let mut builder = tar::Builder::new(encoder);
+    for name in ["out/minikube", "out/docker-machine-driver-kvm2"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(name.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, name, name.as_bytes())?;
+    }
+    builder.into_inner()?.finish()?;
+
+    // members are listed in a different order than archived, and one of
+    // them is extracted twice
+    let members = [
+        (
+            "out/docker-machine-driver-kvm2".to_string(),
+            tempdir.path().join("kvm2"),
+        ),
+        ("out/minikube".to_string(), tempdir.path().join("minikube")),
+        ("out/minikube".to_string(), tempdir.path().join("mk")),
+    ];
+    extract(ArchiveFormat::TarGz, &archive_path, &members)
+        .map_err(|e| e as Error)?;
+    for (member, dest) in &members {
+        assert_eq!(fs::read_to_string(dest)?, *member);
+    }
+    Ok(())
+}
+
+/// an uncompressed tar archive containing `tool`
+fn tar_archive(content: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut builder = tar::Builder::new(vec![]);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder.append_data(&mut header, "bin/tool", content)?;
+    Ok(builder.into_inner()?)
+}
+
+#[test]
+fn test_extract_formats() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let content = b"#!/bin/sh\necho tool\n";
+    let tar = tar_archive(content)?;
+
+    let mut xz = xz2::write::XzEncoder::new(vec![], 6);
+    xz.write_all(&tar)?;
+    let mut gzip = GzEncoder::new(vec![], Compression::default());
+    gzip.write_all(content)?;
+    let mut zip = zip::ZipWriter::new(io::Cursor::new(vec![]));
+    zip.start_file("bin/tool", zip::write::FileOptions::default())?;
+    zip.write_all(content)?;
+    let archives = [
+        (ArchiveFormat::TarXz, xz.finish()?),
+        (ArchiveFormat::TarZst, zstd::encode_all(&tar[..], 0)?),
+        (ArchiveFormat::Zip, zip.finish()?.into_inner()),
+        (ArchiveFormat::Gzip, gzip.finish()?),
+    ];
+    for (format, archive) in archives {
+        let archive_path = tempdir.path().join("package");
+        fs::write(&archive_path, archive)?;
+        let dest = tempdir.path().join(format!("{:?}", format));
+        let members = [("bin/tool".to_string(), dest.clone())];
+        extract(format, &archive_path, &members).map_err(|e| e as Error)?;
+        assert_eq!(fs::read(&dest)?, content, "{:?}", format);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streaming_extractor() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let content = b"#!/bin/sh\necho tool\n";
+    let mut gzip = GzEncoder::new(vec![], Compression::default());
+    gzip.write_all(&tar_archive(content)?)?;
+    let archive = gzip.finish()?;
+    let dest = tempdir.path().join("tool");
+    let members = vec![("bin/tool".to_string(), dest.clone())];
+    let mut extractor = StreamingExtractor::new(ArchiveFormat::TarGz, members);
+
+    // an interrupted first attempt, then a resumed one
+    let path = tempdir.path().join("package");
+    fs::write(&path, "")?;
+    extractor.restart(&path, 0).await?;
+    extractor.write(&archive[..10]).await;
+    fs::write(&path, &archive[..10])?;
+    extractor.restart(&path, 10).await?;
+    // more chunks than the extractor buffers
+    for chunk in archive[10..].chunks(4) {
+        extractor.write(chunk).await;
+    }
+    extractor.finish(&path).await?;
+    assert_eq!(fs::read(&dest)?, content);
+    Ok(())
+}
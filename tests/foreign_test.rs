@@ -0,0 +1,43 @@
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::VersionRef;
+use neatkube::toolbox::Toolbox;
+
+mod common;
+
+#[tokio::test]
+async fn test_foreign_platform_stays_offline() -> Result<(), Error> {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let seen = requests.clone();
+    let url = common::serve(move |_| {
+        seen.fetch_add(1, Ordering::SeqCst);
+        common::response("404 Not Found", &[], b"")
+    });
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    // any download would reach the local server instead
+    let config = format!(
+        "rewrites:\n  - prefix: https://\n    replacement: {}/\n",
+        url
+    );
+    fs::write(tempdir.path().join("config.yaml"), config)?;
+
+    // like `nk --offline toolbox --os linux --arch arm64 install helm`
+    let toolbox = Toolbox::create(Platform::current()).await?;
+    toolbox.downloader().set_offline(true);
+    let foreign = toolbox.with_platform("linux-arm64".parse()?).await?;
+    assert!(foreign.downloader().is_offline());
+    let tool = foreign.tool_with_version(
+        "helm",
+        [VersionRef::Specific("v3.11.2".to_string())],
+    )?;
+    let error = tool.install(false).await.unwrap_err();
+    assert!(error.to_string().contains("Offline mode"), "{}", error);
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+    Ok(())
+}
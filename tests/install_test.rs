@@ -0,0 +1,46 @@
+use neatkube::cmd::output::Action;
+use neatkube::cmd::toolbox::install_all;
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::VersionRef;
+use neatkube::toolbox::Toolbox;
+
+mod common;
+
+#[tokio::test]
+async fn test_install_all() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    // every download fails, without waiting for the network
+    std::env::set_var("NK_OFFLINE", "1");
+    common::install_fake(tempdir.path(), "helm", "v3.11.2")?;
+    common::install_fake(tempdir.path(), "yq", "v4.33.3")?;
+
+    let toolbox = Toolbox::create(Platform::current()).await?;
+    let specific = |v: &str| [VersionRef::Specific(v.to_string())];
+    let tools = [
+        toolbox.tool_with_version("yq", specific("v4.33.3"))?,
+        toolbox.tool_with_version("dyff", specific("v1.5.7"))?,
+        toolbox.tool_with_version("helm", specific("v3.11.2"))?,
+    ];
+    let records = install_all(&tools, false, 3, Action::Installed).await;
+
+    // sorted by name, whichever finished first, and the failing tool
+    // doesn't stop the others
+    let summary: Vec<_> = records
+        .iter()
+        .map(|r| (r.name.as_str(), r.version.as_deref(), r.action))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("dyff", Some("v1.5.7"), Action::Failed),
+            ("helm", Some("v3.11.2"), Action::Unchanged),
+            ("yq", Some("v4.33.3"), Action::Unchanged),
+        ]
+    );
+    assert!(records[0].error.as_ref().unwrap().contains("Offline mode"));
+    assert!(records[1].path.is_some());
+    Ok(())
+}
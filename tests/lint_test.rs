@@ -0,0 +1,98 @@
+use neatkube::toolbox::lint::lint;
+use neatkube::toolbox::repository::Repository;
+use neatkube::toolbox::REPOSITORY;
+
+#[test]
+fn test_lint_builtin_repository() {
+    let repository = Repository::from_slice(REPOSITORY).unwrap();
+    assert_eq!(lint(&repository), Vec::<String>::new());
+}
+
+#[test]
+fn test_lint() {
+    let repository = Repository::from_slice(
+        br#"
+default:
+  with_subcommand: kubectl
+  no_subcommand: foo
+tools:
+- name: foo
+  description: foo
+  aliases: [f]
+  dependencies: [bar]
+  upstream:
+    simple:
+      version_url: https://example.com/version
+      package_url: https://example.com/{{version}}/{{platform}}
+- name: baz
+  description: baz
+  aliases: [f]
+  binaries:
+  - name: qux
+    path: qux
+  upstream:
+    github_release:
+      repo: example/baz
+      file: baz_{{os}}_{{arch}}
+"#,
+    )
+    .unwrap();
+    let problems = lint(&repository);
+    assert_eq!(
+        problems[..3],
+        [
+            "default.with_subcommand: unknown tool kubectl",
+            "f is used by both foo and baz",
+            "Unknown dependency bar of foo",
+        ]
+    );
+    assert!(problems[3].starts_with("foo: package_url: "));
+    assert_eq!(
+        problems[4..],
+        ["baz: binaries can only be installed from an archive"]
+    );
+}
+
+#[test]
+fn test_unknown_fields() {
+    let result = Repository::from_slice(
+        br#"
+default: {with_subcommand: foo, no_subcommand: foo}
+tools:
+- name: foo
+  description: foo
+  upstream:
+    github_release:
+      repo: example/foo
+      fiel: foo
+"#,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_lint_redundant_binary() {
+    let repository = Repository::from_slice(
+        br#"
+default: {with_subcommand: istio, no_subcommand: istio}
+tools:
+- name: istio
+  description: istio
+  upstream:
+    github_release:
+      repo: istio/istio
+      file: istio-{{version}}.tar.gz
+  extract:
+    format: tar.gz
+    path: bin/istioctl
+  binaries:
+  - name: istioctl
+    path: bin/istioctl
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        lint(&repository),
+        ["istio: binary istioctl is extract.path, use an alias instead"]
+    );
+}
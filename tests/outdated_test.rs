@@ -0,0 +1,18 @@
+use neatkube::cmd::outdated::{is_behind, parse_timestamp};
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_timestamp("2023-03-08T19:56:16Z"), Some(1678305376));
+    assert_eq!(parse_timestamp("2024-02-29T00:00:00Z"), Some(1709164800));
+    assert_eq!(parse_timestamp("yesterday"), None);
+}
+
+#[test]
+fn test_is_behind() {
+    assert!(!is_behind("v3.9.0", "v3.11.2", 2));
+    assert!(is_behind("v3.8.4", "v3.11.2", 2));
+    assert!(is_behind("v1.26.0", "v2.0.0", 2));
+    assert!(!is_behind("v3.11.2", "v3.11.2", 0));
+    assert!(!is_behind("latest", "v3.11.2", 0));
+}
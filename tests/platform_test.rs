@@ -0,0 +1,52 @@
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::repository::Repository;
+
+#[test]
+fn test_render_for_platform() {
+    let repository = Repository::from_slice(
+        br#"
+default: {with_subcommand: k9s, no_subcommand: k9s}
+tools:
+- name: k9s
+  description: k9s
+  upstream:
+    github_release:
+      repo: derailed/k9s
+      file: k9s_{{os}}_{{arch}}.tar.gz
+  os_map:
+    darwin: Darwin
+  arch_map:
+    amd64: x86_64
+  os_arch_map:
+    Darwin-x86_64: Darwin_universal
+"#,
+    )
+    .unwrap();
+    let k9s = &repository.tools()[0];
+    let url = |os, arch| {
+        k9s.package_url("v0.27.3", &Platform::new(os, arch))
+            .unwrap()
+    };
+    assert!(url("linux", "arm64").ends_with("/k9s_linux_arm64.tar.gz"));
+    assert!(url("darwin", "arm64").ends_with("/k9s_Darwin_arm64.tar.gz"));
+    assert!(url("linux", "amd64").ends_with("/k9s_linux_x86_64.tar.gz"));
+    assert_eq!(
+        k9s.os_arch(&Platform::new("darwin", "amd64")),
+        "Darwin_universal"
+    );
+}
+
+#[test]
+fn test_parse_platform() {
+    let parse = |os, arch| Platform::parse(os, arch).map(|p| p.to_string());
+    assert_eq!(parse("macos", "aarch64").unwrap(), "darwin-arm64");
+    assert_eq!(parse("linux", "x86_64").unwrap(), "linux-amd64");
+    assert_eq!(parse("linux", "armv7").unwrap(), "linux-arm");
+    assert!(parse("linux", "sparc").is_err());
+    assert!(parse("plan9", "amd64").is_err());
+    assert_eq!(
+        "linux-arm64".parse::<Platform>().unwrap(),
+        Platform::new("linux", "arm64")
+    );
+    assert!("linux".parse::<Platform>().is_err());
+}
@@ -0,0 +1,60 @@
+use neatkube::error::Error;
+use neatkube::project::{Project, PROJECT_FILE_NAME};
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::ToolDefinition;
+
+#[test]
+fn test_project_pins() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join(PROJECT_FILE_NAME);
+    std::fs::write(&path, "tools:\n  helm: v3.11.2\n  kubectl: v1.24.*\n")?;
+
+    let project = Project::load(&path)?;
+    assert_eq!(project.pinned_version("helm"), Some("v3.11.2"));
+    assert_eq!(project.pinned_version("kubectl"), Some("v1.24.*"));
+    assert_eq!(project.pinned_version("k9s"), None);
+
+    std::fs::write(&path, "tools:\n  helm: ^3.x.1\n")?;
+    let err = Project::load(&path).err().unwrap();
+    assert!(err
+        .to_string()
+        .contains("tools.helm: Malformed version range"));
+    Ok(())
+}
+
+#[test]
+fn test_project_variables() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join(PROJECT_FILE_NAME);
+    std::fs::write(&path, "variables:\n  kube_context: staging\n")?;
+    let project = Project::load(&path)?;
+
+    let helmfile: ToolDefinition = serde_yaml::from_str(
+        r#"
+name: helmfile
+description: helmfile
+upstream:
+  github_release:
+    repo: helmfile/helmfile
+    file: helmfile_{{stripped_version}}_{{os}}_{{arch}}.tar.gz
+env:
+  HELMFILE_VERSION: "{{version}}"
+args: [--kube-context, "{{kube_context}}"]
+"#,
+    )?;
+    let platform = Platform::new("linux", "amd64");
+    let render = |template: &str| {
+        helmfile.replace_with(
+            template,
+            "v0.151.0",
+            &platform,
+            project.variables(),
+        )
+    };
+    assert_eq!(render(&helmfile.args()[1])?, "staging");
+    assert_eq!(render(&helmfile.env()["HELMFILE_VERSION"])?, "v0.151.0");
+    assert!(helmfile
+        .replace(&helmfile.args()[1], "v0.151.0", &platform)
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::fs;
+
+use neatkube::cmd::self_update::definition;
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+
+static RELEASE_URL: &str =
+    "https://github.com/withlazers/neatkube/releases/download/v0.5.4";
+
+#[test]
+fn test_release_asset() -> Result<(), Error> {
+    let definition = definition()?;
+    let platform: Platform = "linux-amd64".parse()?;
+    assert_eq!(
+        definition.package_url("v0.5.4", &platform)?,
+        format!(
+            "{}/neatkube-v0.5.4-x86_64-unknown-linux-musl.tar.gz",
+            RELEASE_URL
+        )
+    );
+    let path = definition.extract().unwrap().path();
+    assert_eq!(
+        definition.replace(path, "v0.5.4", &platform)?,
+        "neatkube-v0.5.4-x86_64-unknown-linux-musl/bin/nk"
+    );
+    Ok(())
+}
+
+/// the assets are named after the `archive-prefix` of the build action and
+/// the targets of the release workflow
+#[test]
+fn test_release_workflow() -> Result<(), Error> {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let action = fs::read_to_string(format!(
+        "{}/.github/actions/rust-build/action.yaml",
+        root
+    ))?;
+    let prefix = "archive-prefix: neatkube-${{ github.ref_name }}";
+    assert!(action.contains(prefix));
+    let workflow =
+        fs::read_to_string(format!("{}/.github/workflows/release.yaml", root))?;
+
+    let definition = definition()?;
+    for platform in [
+        "linux-amd64",
+        "linux-arm64",
+        "linux-386",
+        "linux-arm",
+        "darwin-amd64",
+        "darwin-arm64",
+    ] {
+        let platform: Platform = platform.parse()?;
+        let target = definition.os_arch(&platform);
+        assert!(workflow.contains(&format!("- {}\n", target)), "{}", target);
+        let file = format!("neatkube-v1.0.0-{}.tar.gz", target);
+        let url = definition.package_url("v1.0.0", &platform)?;
+        assert!(url.ends_with(&format!("/v1.0.0/{}", file)), "{}", url);
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+
+use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
+use neatkube::toolbox::tool::VersionRef;
+use neatkube::toolbox::Toolbox;
+
+mod common;
+
+static REPOSITORY: &str = r#"
+default: {with_subcommand: a, no_subcommand: b}
+tools:
+  - name: outer
+    description: depends on inner
+    dependencies: [inner]
+    env: {OUTER_COLOR: red, OUTER_SIZE: small, SHARED: outer}
+    args: [--from-definition]
+    upstream:
+      github_release: {repo: a/b, file: c}
+  - name: inner
+    description: run by outer
+    env: {INNER_COLOR: blue, SHARED: inner}
+    upstream:
+      github_release: {repo: a/b, file: c}
+"#;
+
+static CONFIG: &str = r#"
+tools:
+  outer:
+    env: {OUTER_SIZE: "large-{{version}}"}
+    args: [--from-config]
+"#;
+
+#[tokio::test]
+async fn test_tool_settings() -> Result<(), Error> {
+    let tempdir = tempfile::tempdir()?;
+    std::env::set_var("NK_DATA_DIR", tempdir.path());
+    std::env::set_var("NK_CONFIG_DIR", tempdir.path());
+    std::env::set_var("OUTER_COLOR", "green");
+    fs::create_dir_all(tempdir.path().join("repositories"))?;
+    fs::write(tempdir.path().join("repositories/test.yaml"), REPOSITORY)?;
+    fs::write(tempdir.path().join("config.yaml"), CONFIG)?;
+    common::install_fake(tempdir.path(), "outer", "v1.0.0")?;
+    common::install_fake(tempdir.path(), "inner", "v1.0.0")?;
+
+    let toolbox = Toolbox::create(Platform::current()).await?;
+    let version = VersionRef::Specific("v1.0.0".to_string());
+    let tool = toolbox.tool_with_version("outer", [version])?;
+
+    let command = tool.command(["arg"]).await?;
+    let args: Vec<_> = command.as_std().get_args().collect();
+    // the args of the config replace the ones of the definition
+    assert_eq!(args, ["--from-config", "arg"]);
+    let env: HashMap<_, _> = command.as_std().get_envs().collect();
+    let var = |name: &str| env.get(OsStr::new(name)).copied().flatten();
+    // already set in the environment
+    assert_eq!(var("OUTER_COLOR"), None);
+    // the env of the config wins over the one of the definition
+    assert_eq!(var("OUTER_SIZE"), Some(OsStr::new("large-v1.0.0")));
+    // dependencies get their env, but the tool's own one wins
+    assert_eq!(var("INNER_COLOR"), Some(OsStr::new("blue")));
+    assert_eq!(var("SHARED"), Some(OsStr::new("outer")));
+    // dependencies are found through PATH
+    let path = var("PATH").unwrap().to_str().unwrap();
+    assert!(path.contains("exec/inner/v1.0.0"));
+
+    let command = tool.plain_command(["arg"]).await?;
+    let args: Vec<_> = command.as_std().get_args().collect();
+    assert_eq!(args, ["arg"]);
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use neatkube::config::Config;
+use neatkube::download::Downloader;
+use neatkube::error::Error;
+
+mod common;
+
+#[tokio::test]
+async fn test_token_is_only_sent_to_github() -> Result<(), Error> {
+    std::env::set_var("GITHUB_TOKEN", "secret");
+    // the proxy sees the requests to all hosts
+    let proxy = common::serve(|request| {
+        let authorization = request.header("Authorization").unwrap_or("none");
+        common::response("200 OK", &[], authorization.as_bytes())
+    });
+    let config = format!("proxy: {}\n", proxy);
+    let downloader = Downloader::new(&Config::from_slice(config.as_bytes())?)?;
+
+    for url in ["http://api.github.com/repos", "http://github.com/releases"] {
+        assert_eq!(downloader.string(url, "test").await?, "Bearer secret");
+    }
+    for url in [
+        "http://get.helm.sh/helm.tar.gz",
+        "http://github.com.example.com/releases",
+        "http://objects.githubusercontent.com/asset",
+    ] {
+        assert_eq!(downloader.string(url, "test").await?, "none", "{}", url);
+    }
+    Ok(())
+}
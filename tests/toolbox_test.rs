@@ -1,4 +1,5 @@
 use neatkube::error::Error;
+use neatkube::toolbox::platform::Platform;
 use neatkube::toolbox::tool::Tool;
 use neatkube::toolbox::Toolbox;
 use tokio::test;
@@ -8,13 +9,9 @@ async fn test_toolbox() -> Result<(), Error> {
     let tempdir = tempfile::tempdir()?;
     std::env::set_var("NK_DATA_DIR", tempdir.path().to_str().unwrap());
 
-    let toolbox = Toolbox::create().await?;
+    let toolbox = Toolbox::create(Platform::current()).await?;
 
     for tool in toolbox.repository().tools() {
-        if tool.name() == "docker-machine-driver-kvm2" {
-            println!("Ignoring Tool: {}", tool.name());
-            continue;
-        }
         let tool = Tool::new(tool, &toolbox);
         let mut command = tool.command(["--help"]).await?;
         let exit_status = command.spawn()?.wait().await?.code().unwrap();
@@ -0,0 +1,50 @@
+use neatkube::toolbox::upstream::{parse_release_list, Release};
+
+fn release(version: &str, prerelease: bool) -> Release {
+    Release {
+        version: version.to_string(),
+        prerelease,
+        published_at: None,
+        notes: None,
+    }
+}
+
+#[test]
+fn test_parse_github_releases() {
+    let response = r#"[
+        {"tag_name": "v3.12.0-rc.1", "draft": false, "prerelease": true},
+        {"tag_name": "v3.12.0", "draft": true, "prerelease": false},
+        {"tag_name": "v3.11.2", "draft": false, "prerelease": false}
+    ]"#;
+    assert_eq!(
+        parse_release_list(response).unwrap(),
+        vec![release("v3.12.0-rc.1", true), release("v3.11.2", false)]
+    );
+    assert!(parse_release_list(r#"{"message": "Not Found"}"#).is_err());
+
+    let response = r#"[{
+        "tag_name": "v3.11.2",
+        "published_at": "2023-03-08T19:56:16Z",
+        "body": "fixes"
+    }]"#;
+    let release = &parse_release_list(response).unwrap()[0];
+    assert_eq!(
+        release.published_at.as_deref(),
+        Some("2023-03-08T19:56:16Z")
+    );
+    assert_eq!(release.notes.as_deref(), Some("fixes"));
+}
+
+#[test]
+fn test_parse_version_lists() {
+    let expected =
+        vec![release("v1.25.0", false), release("v1.26.0-alpha.1", true)];
+    assert_eq!(
+        parse_release_list("v1.25.0\n# comment\n\nv1.26.0-alpha.1\n").unwrap(),
+        expected
+    );
+    assert_eq!(
+        parse_release_list(r#"["v1.25.0", "v1.26.0-alpha.1"]"#).unwrap(),
+        expected
+    );
+}
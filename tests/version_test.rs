@@ -0,0 +1,66 @@
+use neatkube::toolbox::tool::{
+    highest_in_skew, parse_semver, parse_tool_spec, VersionRef,
+};
+
+#[test]
+fn test_version_ref_range() {
+    let range = VersionRef::parse_range("^3.10").unwrap().unwrap();
+    assert!(range.matches(&parse_semver("v3.11.2").unwrap()));
+    assert!(!range.matches(&parse_semver("v4.0.0").unwrap()));
+
+    let range = VersionRef::parse_range("v1.24.*").unwrap().unwrap();
+    assert!(range.matches(&parse_semver("v1.24.3").unwrap()));
+    assert!(!range.matches(&parse_semver("v1.25.0").unwrap()));
+
+    let range = VersionRef::parse_range("~v1.24").unwrap().unwrap();
+    assert!(range.matches(&parse_semver("1.24.9").unwrap()));
+
+    assert!(VersionRef::parse_range("v3.11.2").unwrap().is_none());
+    assert!(VersionRef::parse_range("^3.x.y").is_err());
+    assert!(matches!(
+        "v3.11.2".parse::<VersionRef>().unwrap(),
+        VersionRef::Specific(_)
+    ));
+    assert!(matches!(
+        ">=1.22".parse::<VersionRef>().unwrap(),
+        VersionRef::Range(_)
+    ));
+}
+
+#[test]
+fn test_parse_tool_spec() {
+    let (name, version_ref) = parse_tool_spec("helm@^3.10").unwrap();
+    assert_eq!(name, "helm");
+    assert!(matches!(version_ref, Some(VersionRef::Range(_))));
+
+    let (name, version_ref) = parse_tool_spec("kubectl").unwrap();
+    assert_eq!(name, "kubectl");
+    assert!(version_ref.is_none());
+
+    let err = parse_tool_spec("helm@^3.1O").err().unwrap();
+    assert!(err.to_string().contains("Malformed version range ^3.1O"));
+}
+
+#[test]
+fn test_highest_in_skew() {
+    let versions = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+    let installed = versions(&["v1.23.9", "v1.25.1", "v1.25.4", "v1.26.0"]);
+    assert_eq!(
+        highest_in_skew(installed, 1, 25),
+        Some("v1.25.4".to_string())
+    );
+    // one minor newer is preferred over one older
+    let installed = versions(&["v1.23.9", "v1.25.1"]);
+    assert_eq!(
+        highest_in_skew(installed, 1, 24),
+        Some("v1.25.1".to_string())
+    );
+    let installed = versions(&["v1.23.9", "v1.27.0"]);
+    assert_eq!(
+        highest_in_skew(installed, 1, 24),
+        Some("v1.23.9".to_string())
+    );
+    let installed = versions(&["v1.22.0", "v1.27.0"]);
+    assert_eq!(highest_in_skew(installed, 1, 24), None);
+    assert_eq!(highest_in_skew(versions(&["v1.2.0"]), 1, 0), None);
+}